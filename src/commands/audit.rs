@@ -0,0 +1,212 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::commands::{CleanupError, Env, ProbeError, Report, Result, Utils};
+
+/// 删除的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Removed,
+    Failed,
+}
+
+/// 审计日志中的一条删除记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix 秒
+    pub timestamp: u64,
+    /// 清理目标，如 `rust`、`simulator` 或自定义目标名
+    pub target: String,
+    pub item: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// 删除前的可回收大小（字节）
+    pub size: u64,
+    /// 执行的外部命令；直接删除文件时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    pub outcome: Outcome,
+    /// 失败时外部命令的 stderr 或错误信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(target: &str, item: &str, size: u64) -> Self {
+        AuditEntry {
+            timestamp: 0,
+            target: target.to_string(),
+            item: item.to_string(),
+            path: None,
+            size,
+            command: None,
+            outcome: Outcome::Removed,
+            stderr: None,
+        }
+    }
+
+    pub fn path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    pub fn command(mut self, command: String) -> Self {
+        self.command = Some(command);
+        self
+    }
+}
+
+/// `cleanup log` 的查询条件
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub target: Option<String>,
+    /// 条目名称的 glob
+    pub item: Option<glob::Pattern>,
+    /// 只显示该时间（Unix 秒）之后的记录
+    pub since: Option<u64>,
+    pub failed: bool,
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.target
+            .as_ref()
+            .is_none_or(|target| &entry.target == target)
+            && self
+                .item
+                .as_ref()
+                .is_none_or(|item| item.matches(&entry.item))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && (!self.failed || entry.outcome == Outcome::Failed)
+    }
+}
+
+/// 只追加的删除审计日志
+pub struct Audit;
+
+impl Audit {
+    /// 日志位置：数据目录下的 `audit.jsonl`
+    fn log_path(env: &Env) -> Result<PathBuf> {
+        Ok(env.data_dir()?.join("audit.jsonl"))
+    }
+
+    /// 执行一次删除并记录结果；日志写入失败不影响删除结果，但会计入本次运行的错误
+    pub fn track(env: &Env, entry: AuditEntry, remove: impl FnOnce() -> Result<()>) -> Result<()> {
+        let result = remove();
+        let mut entry = entry;
+        entry.timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if let Err(err) = &result {
+            entry.outcome = Outcome::Failed;
+            entry.stderr = Some(match err {
+                CleanupError::Command {
+                    source: ProbeError::Failed { stderr, .. },
+                    ..
+                } if !stderr.trim().is_empty() => stderr.trim().to_string(),
+                err => err.to_string(),
+            });
+        }
+        if let Err(err) =
+            Self::log_path(env).and_then(|path| Utils::append_json_line(&path, &entry))
+        {
+            eprintln!("{} Audit log: {}", "⚠".yellow(), err);
+            Report::record(err);
+        }
+        result
+    }
+
+    /// 按记录顺序读取所有删除记录
+    pub fn load(env: &Env) -> Result<Vec<AuditEntry>> {
+        Utils::read_json_lines(&Self::log_path(env)?)
+    }
+
+    /// 显示符合条件的最近 `limit` 条记录
+    pub fn show_log(env: &Env, query: &AuditQuery) -> Result<()> {
+        println!("{}", "📜 Deletion Log:".bold().cyan());
+        println!();
+        let entries: Vec<AuditEntry> = Self::load(env)?
+            .into_iter()
+            .filter(|entry| query.matches(entry))
+            .collect();
+        if entries.is_empty() {
+            println!("No matching deletions recorded.");
+            return Ok(());
+        }
+
+        let start = entries.len().saturating_sub(query.limit.max(1));
+        for entry in &entries[start..] {
+            let outcome = match entry.outcome {
+                Outcome::Removed => "✓".green(),
+                Outcome::Failed => "✗".red(),
+            };
+            println!(
+                "{} {}  {:<12} {:<45} {:>10}",
+                outcome,
+                Utils::format_date(entry.timestamp).dimmed(),
+                entry.target,
+                entry.item,
+                Utils::format_size(entry.size).yellow()
+            );
+            if let Some(path) = &entry.path {
+                println!("      path:    {}", path.display());
+            }
+            if let Some(command) = &entry.command {
+                println!("      command: {}", command);
+            }
+            if let Some(stderr) = &entry.stderr {
+                println!("      error:   {}", stderr.red());
+            }
+        }
+        println!();
+        println!(
+            "Showing {} of {} matching deletions.",
+            entries.len() - start,
+            entries.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_filters_by_target_item_time_and_outcome() {
+        let mut removed = AuditEntry::new("rust", "1.75.0-aarch64-apple-darwin", 10);
+        removed.timestamp = 2_000;
+        let mut failed = AuditEntry::new("solana", "1.18.0", 20);
+        failed.timestamp = 1_000;
+        failed.outcome = Outcome::Failed;
+
+        let all = AuditQuery::default();
+        assert!(all.matches(&removed) && all.matches(&failed));
+
+        let rust = AuditQuery {
+            target: Some("rust".to_string()),
+            item: Some(glob::Pattern::new("1.75*").expect("glob")),
+            ..AuditQuery::default()
+        };
+        assert!(rust.matches(&removed) && !rust.matches(&failed));
+
+        let recent = AuditQuery {
+            since: Some(1_500),
+            ..AuditQuery::default()
+        };
+        assert!(recent.matches(&removed) && !recent.matches(&failed));
+
+        let failures = AuditQuery {
+            failed: true,
+            ..AuditQuery::default()
+        };
+        assert!(!failures.matches(&removed) && failures.matches(&failed));
+    }
+}
@@ -0,0 +1,221 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::commands::{CleanupError, DirSize, Env, LastUse, Result, Scan, Utils};
+
+const CACHE_VERSION: u32 = 2;
+
+/// 单个扫描根目录的缓存记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: DirSize,
+    last_use: LastUse,
+    /// 树中每个目录及其修改时间，任意一个变化都说明需要重新扫描
+    dirs: Vec<(PathBuf, u64)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+struct ScanCache {
+    /// 缓存文件位置；为 `None` 时只在内存中缓存
+    path: Option<PathBuf>,
+    file: Mutex<CacheFile>,
+    dirty: AtomicBool,
+    refresh: bool,
+}
+
+static SCAN_CACHE: OnceLock<ScanCache> = OnceLock::new();
+
+/// 基于目录修改时间的磁盘扫描缓存
+pub struct Cache;
+
+impl Cache {
+    /// 缓存文件位置：`$XDG_CACHE_HOME/cleanup/scan-cache.json`，默认 `~/.cache/cleanup`
+    fn cache_path(env: &Env) -> Option<PathBuf> {
+        let base = match env.var("XDG_CACHE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => env.home().ok()?.join(".cache"),
+        };
+        Some(base.join("cleanup/scan-cache.json"))
+    }
+
+    fn load(path: &Path) -> CacheFile {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    fn get() -> &'static ScanCache {
+        SCAN_CACHE.get_or_init(|| ScanCache {
+            path: None,
+            file: Mutex::new(CacheFile::default()),
+            dirty: AtomicBool::new(false),
+            refresh: false,
+        })
+    }
+
+    /// 初始化缓存；`refresh` 为 true 时忽略已有记录并重新扫描所有目录
+    pub fn init(env: &Env, refresh: bool) {
+        let path = Self::cache_path(env);
+        let file = match path.as_deref() {
+            Some(path) if !refresh => Self::load(path),
+            _ => CacheFile::default(),
+        };
+        let _ = SCAN_CACHE.set(ScanCache {
+            path,
+            file: Mutex::new(file),
+            dirty: AtomicBool::new(false),
+            refresh,
+        });
+    }
+
+    /// 校验缓存记录中的所有目录修改时间是否未变
+    fn is_fresh(entry: &CacheEntry) -> bool {
+        !entry.dirs.is_empty()
+            && entry.dirs.iter().all(|(dir, mtime)| {
+                fs::symlink_metadata(dir)
+                    .ok()
+                    .filter(|metadata| metadata.is_dir())
+                    .and_then(|metadata| Utils::modified_nanos(&metadata))
+                    == Some(*mtime)
+            })
+    }
+
+    /// 获取目录占用与最近使用时间，目录树未变化时直接返回缓存结果；
+    /// 文件访问时间不会使缓存失效，缓存命中时只有根路径本身的时间会重新读取，
+    /// 需要准确的访问时间时应以 `refresh` 初始化缓存
+    pub fn measure_dir(dir: &Path) -> (DirSize, LastUse) {
+        let cache = Self::get();
+
+        if !cache.refresh {
+            let cached = cache
+                .file
+                .lock()
+                .ok()
+                .and_then(|file| file.entries.get(dir).cloned());
+            if let Some(entry) = cached.filter(Self::is_fresh) {
+                let root = fs::metadata(dir)
+                    .map(|metadata| LastUse::of(&metadata))
+                    .unwrap_or_default();
+                return (entry.size, entry.last_use.merge(root));
+            }
+        }
+
+        let scan = Utils::scan_dir(dir);
+        if Scan::is_cancelled() {
+            return (scan.size, scan.last_use);
+        }
+        if let Ok(mut file) = cache.file.lock() {
+            if scan.dirs.is_empty() {
+                file.entries.remove(dir);
+            } else {
+                file.entries.insert(
+                    dir.to_path_buf(),
+                    CacheEntry {
+                        size: scan.size,
+                        last_use: scan.last_use,
+                        dirs: scan.dirs,
+                    },
+                );
+            }
+            cache.dirty.store(true, Ordering::Relaxed);
+        }
+        (scan.size, scan.last_use)
+    }
+
+    /// 将本次运行中更新过的缓存写回磁盘
+    pub fn save() {
+        let Some(cache) = SCAN_CACHE.get() else {
+            return;
+        };
+        if !cache.dirty.load(Ordering::Relaxed) {
+            return;
+        }
+        let Some(path) = cache.path.as_ref() else {
+            return;
+        };
+        let Ok(mut file) = cache.file.lock() else {
+            return;
+        };
+        file.version = CACHE_VERSION;
+        file.entries.retain(|dir, _| dir.exists());
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                serde_json::to_vec(&*file)
+                    .map_err(std::io::Error::other)
+                    .and_then(|bytes| fs::write(path, bytes))
+            });
+        if let Err(err) = result {
+            eprintln!("Failed to write scan cache {}: {}", path.display(), err);
+        }
+    }
+
+    /// 删除磁盘上的扫描缓存
+    pub fn clear() -> Result<()> {
+        let cache = Self::get();
+        let path = cache.path.as_ref().ok_or(CleanupError::MissingHome)?;
+        if let Ok(mut file) = cache.file.lock() {
+            file.entries.clear();
+            cache.dirty.store(false, Ordering::Relaxed);
+        }
+
+        match fs::remove_file(path) {
+            Ok(_) => println!("{} {}", "✓ Cleared scan cache".green(), path.display()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                println!("Scan cache is already empty.")
+            }
+            Err(err) => return Err(CleanupError::io(path, err)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn cache_entry_goes_stale_when_tree_changes() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("cleanup_cache_test_{}", nanos));
+        let nested = dir.join("nested");
+        fs::create_dir_all(&nested).expect("Failed to create temp directory");
+        fs::write(nested.join("a.bin"), [0u8; 16]).expect("Failed to write file");
+
+        let scan = Utils::scan_dir(&dir);
+        let entry = CacheEntry {
+            size: scan.size,
+            last_use: scan.last_use,
+            dirs: scan.dirs,
+        };
+        assert!(Cache::is_fresh(&entry));
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        fs::write(nested.join("b.bin"), [0u8; 16]).expect("Failed to write file");
+        assert!(!Cache::is_fresh(&entry));
+
+        fs::remove_dir_all(&dir).expect("Failed to remove temp directory");
+    }
+}
@@ -0,0 +1,422 @@
+use crate::commands::{
+    Audit, AuditEntry, Backend, CleanupError, Config, DirSize, Env, LastUse, Removal, Removals,
+    Result, Scan, ScanEvent, Utils,
+};
+use colored::Colorize;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub struct CargoBin;
+
+/// `cargo install` 安装的一个 crate
+#[derive(Debug)]
+pub struct CrateInfo {
+    pub name: String,
+    pub version: String,
+    /// 安装来源，如 `registry+https://github.com/rust-lang/crates.io-index`
+    pub source: String,
+    /// 在 `.crates.toml` / `.crates2.json` 中的键
+    pub key: String,
+    pub binaries: Vec<PathBuf>,
+    pub size: DirSize,
+    /// 二进制的修改时间即安装时间，访问时间反映最近一次运行
+    pub last_use: LastUse,
+    /// 构建它的 rustc 版本，如 `1.75.0`；只有 `.crates2.json` 记录
+    pub rustc: Option<String>,
+}
+
+/// `.crates2.json` 中与清理相关的字段
+#[derive(Debug, Default, Deserialize)]
+struct Crates2 {
+    #[serde(default)]
+    installs: BTreeMap<String, Install>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Install {
+    #[serde(default)]
+    bins: Vec<String>,
+    #[serde(default)]
+    rustc: Option<String>,
+}
+
+/// `.crates.toml`：`v1` 表中键为 crate，值为二进制名
+#[derive(Debug, Default, Deserialize)]
+struct CratesToml {
+    #[serde(default)]
+    v1: BTreeMap<String, Vec<String>>,
+}
+
+impl CargoBin {
+    fn crates_toml(root: &Path) -> PathBuf {
+        root.join(".crates.toml")
+    }
+
+    fn crates2_json(root: &Path) -> PathBuf {
+        root.join(".crates2.json")
+    }
+
+    /// 读取元数据文件；不存在时为 `None`
+    fn read(path: &Path) -> Result<Option<String>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(CleanupError::io(path, err)),
+        }
+    }
+
+    fn parse_error(path: &Path, message: impl ToString) -> CleanupError {
+        CleanupError::Parse {
+            what: path.display().to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// 合并两份元数据：`.crates2.json` 信息更全，只在 `.crates.toml` 中出现的也列出
+    fn installs(root: &Path) -> Result<BTreeMap<String, Install>> {
+        let json = Self::crates2_json(root);
+        let mut installs = match Self::read(&json)? {
+            Some(text) => {
+                serde_json::from_str::<Crates2>(&text)
+                    .map_err(|err| Self::parse_error(&json, err))?
+                    .installs
+            }
+            None => BTreeMap::new(),
+        };
+        let toml = Self::crates_toml(root);
+        if let Some(text) = Self::read(&toml)? {
+            let crates: CratesToml =
+                toml::from_str(&text).map_err(|err| Self::parse_error(&toml, err))?;
+            for (key, bins) in crates.v1 {
+                installs.entry(key).or_insert(Install { bins, rustc: None });
+            }
+        }
+        Ok(installs)
+    }
+
+    /// 拆分 `ripgrep 14.1.0 (registry+https://...)` 形式的键
+    fn parse_key(key: &str) -> Option<(String, String, String)> {
+        let mut parts = key.splitn(3, ' ');
+        let name = parts.next()?.to_string();
+        let version = parts.next()?.to_string();
+        let source = parts
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .to_string();
+        Some((name, version, source))
+    }
+
+    /// 从 `rustc 1.75.0 (82e1608df 2023-12-21)\nbinary: rustc...` 中取出版本
+    fn parse_rustc(verbose: &str) -> Option<String> {
+        verbose
+            .lines()
+            .next()?
+            .split_whitespace()
+            .nth(1)
+            .map(str::to_string)
+    }
+
+    /// 获取所有已安装的 crate
+    pub fn get_crates(env: &Env) -> Result<Vec<CrateInfo>> {
+        let root = env.settings().cargo_bin.root()?;
+        let installs = Self::installs(&root)?;
+        let filter = &env.settings().filter;
+        Scan::emit(ScanEvent::Discovered(installs.len() as u64));
+
+        let crates = installs
+            .into_iter()
+            .filter_map(|(key, install)| {
+                let (name, version, source) = Self::parse_key(&key)?;
+                let binaries: Vec<PathBuf> = install
+                    .bins
+                    .iter()
+                    .map(|bin| root.join("bin").join(bin))
+                    .collect();
+                let (size, last_use) = binaries
+                    .iter()
+                    .map(|bin| {
+                        let scan = Utils::scan_dir(bin);
+                        (scan.size, scan.last_use)
+                    })
+                    .fold(
+                        (DirSize::default(), LastUse::default()),
+                        |(size, last_use), (bin_size, bin_use)| {
+                            (size + bin_size, last_use.merge(bin_use))
+                        },
+                    );
+                Scan::emit(ScanEvent::Completed);
+                if !filter.matches(&name) || !filter.is_unused(&last_use) {
+                    return None;
+                }
+                Some(CrateInfo {
+                    name,
+                    version,
+                    source,
+                    key,
+                    binaries,
+                    size,
+                    last_use,
+                    rustc: install.rustc.as_deref().and_then(Self::parse_rustc),
+                })
+            })
+            .collect();
+        Ok(crates)
+    }
+
+    /// 格式化单个 crate 的列表行
+    fn format_row(config: &Config, krate: &CrateInfo) -> String {
+        let binaries: Vec<String> = krate
+            .binaries
+            .iter()
+            .filter_map(|bin| bin.file_name()?.to_str().map(str::to_string))
+            .collect();
+        let installed = match krate.last_use.modified {
+            0 => "-".to_string(),
+            modified => Utils::format_date(modified),
+        };
+        format!(
+            "{:<40} {:>10} {:>8}  installed {}  {}  [{}]",
+            format!(
+                "{} {}{}",
+                krate.name,
+                krate.version,
+                config.lock_marker(&krate.name)
+            ),
+            Utils::format_dir_size(&krate.size).yellow(),
+            Utils::format_age(&krate.last_use).dimmed(),
+            installed,
+            krate
+                .rustc
+                .as_deref()
+                .map(|rustc| format!("rustc {}", rustc))
+                .unwrap_or_else(|| "rustc unknown".to_string())
+                .dimmed(),
+            binaries.join(", ")
+        )
+    }
+
+    /// 列出 `cargo install` 安装的 crate
+    pub fn show_crates(env: &Env) -> Result<()> {
+        println!("{}", "📦 Cargo-installed binaries:".bold().cyan());
+        println!();
+        let config = env.settings();
+        if !config.cargo_bin.enabled {
+            println!("Cargo binaries are disabled in config.");
+            return Ok(());
+        }
+        Scan::begin("Cargo binaries");
+        let crates = Self::get_crates(env);
+        Scan::finish();
+        let mut crates = crates?;
+        if crates.is_empty() {
+            println!("No cargo-installed binaries found.");
+            return Ok(());
+        }
+        crates.sort_by_key(|krate| std::cmp::Reverse(krate.size.reclaimable));
+        for krate in &crates {
+            println!("{}", Self::format_row(config, krate));
+        }
+        let total: DirSize = crates.iter().map(|krate| krate.size).sum();
+        println!();
+        println!(
+            "Total: {} crates, {}",
+            crates.len(),
+            Utils::format_dir_size(&total).bold()
+        );
+        Ok(())
+    }
+
+    /// 从两份元数据中删除一个 crate 的记录
+    fn forget(root: &Path, key: &str) -> Result<()> {
+        let toml_path = Self::crates_toml(root);
+        if let Some(text) = Self::read(&toml_path)? {
+            let mut table: toml::Table =
+                toml::from_str(&text).map_err(|err| Self::parse_error(&toml_path, err))?;
+            if let Some(toml::Value::Table(v1)) = table.get_mut("v1") {
+                v1.remove(key);
+            }
+            let text = toml::to_string(&table).map_err(|err| Self::parse_error(&toml_path, err))?;
+            fs::write(&toml_path, text).map_err(|err| CleanupError::io(&toml_path, err))?;
+        }
+
+        let json_path = Self::crates2_json(root);
+        if let Some(text) = Self::read(&json_path)? {
+            let mut value: serde_json::Value =
+                serde_json::from_str(&text).map_err(|err| Self::parse_error(&json_path, err))?;
+            if let Some(installs) = value
+                .get_mut("installs")
+                .and_then(serde_json::Value::as_object_mut)
+            {
+                installs.remove(key);
+            }
+            fs::write(&json_path, value.to_string())
+                .map_err(|err| CleanupError::io(&json_path, err))?;
+        }
+        Ok(())
+    }
+
+    /// 卸载一个 crate：有 cargo 时使用 `cargo uninstall`，否则删除二进制并清理元数据
+    pub fn remove_crate(env: &Env, krate: &CrateInfo) -> Result<()> {
+        let root = env.settings().cargo_bin.root()?;
+        let entry = AuditEntry::new("cargo-bin", &krate.name, krate.size.reclaimable);
+        if env.command_exists("cargo") {
+            let root = root.to_string_lossy().to_string();
+            let args = ["uninstall", "--root", root.as_str(), krate.name.as_str()];
+            let command = format!("cargo {}", args.join(" "));
+            return Audit::track(env, entry.command(command.clone()), || {
+                env.run_removal("cargo", args)
+                    .map(|_| ())
+                    .map_err(|err| CleanupError::command(&command, err))
+            });
+        }
+
+        Audit::track(env, entry.path(&root.join("bin")), || {
+            for bin in &krate.binaries {
+                match fs::remove_file(bin) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                        return Err(CleanupError::io(bin, err));
+                    }
+                    _ => {}
+                }
+            }
+            Self::forget(&root, &krate.key)
+        })
+    }
+
+    /// 清理 `cargo install` 安装的 crate
+    pub fn clean_crates(env: &Env) -> Result<()> {
+        println!("{}", "🧹 Cleaning Cargo Binaries:".bold().cyan());
+        println!();
+        let config = env.settings();
+        if !config.cargo_bin.enabled {
+            println!("Cargo binaries are disabled in config.");
+            return Ok(());
+        }
+        Scan::begin("Cargo binaries");
+        let list = Self::get_crates(env);
+        Scan::finish();
+        let list = list?;
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        let list = config.removable(config.cargo_bin.keep_latest, list.iter().collect(), |c| {
+            (
+                c.name.as_str(),
+                c.binaries.first().map_or(Path::new(""), |b| b),
+            )
+        });
+        if list.is_empty() {
+            println!("No removable cargo-installed binaries.");
+            return Ok(());
+        }
+
+        let items: Vec<String> = list.iter().map(|c| Self::format_row(config, c)).collect();
+        let selections = env
+            .multi_select("Select crates to uninstall:", &items)
+            .map_err(CleanupError::Prompt)?;
+        if selections.is_empty() {
+            println!("No crates selected.");
+            return Ok(());
+        }
+
+        // 两种卸载方式都会改写同一份元数据，必须逐个执行
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let select = &list[index];
+                Removal::new(
+                    "cargo-bin",
+                    &select.name,
+                    select.size.reclaimable,
+                    None,
+                    Backend::Tool("cargo"),
+                    move || Self::remove_crate(env, select),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{Config, ScriptedRunner};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn get_crates_reads_metadata_and_removes_without_cargo() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_cargo_bin_test_{}", nanos));
+        let root = home.join(".cargo");
+        fs::create_dir_all(root.join("bin")).expect("Failed to create bin");
+        for bin in ["rg", "cargo-expand", "just"] {
+            fs::write(root.join("bin").join(bin), [0u8; 2048]).expect("Failed to write binary");
+        }
+        let ripgrep = "ripgrep 14.1.0 (registry+https://github.com/rust-lang/crates.io-index)";
+        let expand = "cargo-expand 1.0.79 (registry+https://github.com/rust-lang/crates.io-index)";
+        let just = "just 1.25.0 (git+https://github.com/casey/just#abc)";
+        fs::write(
+            root.join(".crates.toml"),
+            format!(
+                "[v1]\n\"{}\" = [\"rg\"]\n\"{}\" = [\"cargo-expand\"]\n\"{}\" = [\"just\"]\n",
+                ripgrep, expand, just
+            ),
+        )
+        .expect("Failed to write .crates.toml");
+        fs::write(
+            root.join(".crates2.json"),
+            serde_json::json!({
+                "installs": {
+                    ripgrep: {"bins": ["rg"], "rustc": "rustc 1.75.0 (82e1608df 2023-12-21)\nbinary: rustc\n"},
+                    expand: {"bins": ["cargo-expand"], "rustc": "rustc 1.77.0-nightly (abc 2024-01-10)\n"},
+                }
+            })
+            .to_string(),
+        )
+        .expect("Failed to write .crates2.json");
+
+        let mut config = Config::default().resolve(Some(&home));
+        config.cargo_bin.root = root.clone();
+        let env = Env::with_home(&home)
+            .runner(ScriptedRunner::new())
+            .config(config);
+        let mut crates = CargoBin::get_crates(&env).expect("installed crates");
+        crates.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let summary: Vec<(&str, &str, Option<&str>)> = crates
+            .iter()
+            .map(|c| (c.name.as_str(), c.version.as_str(), c.rustc.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("cargo-expand", "1.0.79", Some("1.77.0-nightly")),
+                ("just", "1.25.0", None),
+                ("ripgrep", "14.1.0", Some("1.75.0")),
+            ]
+        );
+        assert_eq!(crates[1].source, "git+https://github.com/casey/just#abc");
+        assert!(crates[2].size.logical >= 2048);
+
+        CargoBin::remove_crate(&env, &crates[2]).expect("remove ripgrep");
+        assert!(!root.join("bin/rg").exists());
+        let left: Vec<String> = CargoBin::get_crates(&env)
+            .expect("installed crates")
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(left.len(), 2);
+        assert!(!left.contains(&"ripgrep".to_string()));
+        fs::remove_dir_all(&home).expect("Failed to remove temp directory");
+    }
+}
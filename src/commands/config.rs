@@ -0,0 +1,624 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::commands::{CleanupError, Env, LastUse, Result};
+
+/// 内置子命令的名称；自定义目标以名称作为子命令，不能与它们重名
+pub const BUILTIN_COMMANDS: &[&str] = &[
+    "status",
+    "history",
+    "interactive",
+    "tui",
+    "solana",
+    "rust",
+    "simulator",
+    "cargo-bin",
+    "sccache",
+    "target-dir",
+    "solana-ledger",
+    "log",
+    "reclaim",
+    "cache",
+    "config",
+    "help",
+];
+
+/// 编译一个 glob，`what` 用于错误信息
+fn compile_glob(what: &str, glob: &str) -> Result<glob::Pattern> {
+    glob::Pattern::new(glob).map_err(|err| CleanupError::Parse {
+        what: format!("{} glob '{}'", what, glob),
+        message: err.to_string(),
+    })
+}
+
+/// 命令行 `--include`/`--exclude`/`--unused-for` 指定的条目过滤
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+    unused_for: Option<Duration>,
+}
+
+impl Filter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Filter> {
+        Ok(Filter {
+            unused_for: None,
+            include: include
+                .iter()
+                .map(|glob| compile_glob("--include", glob))
+                .collect::<Result<_>>()?,
+            exclude: exclude
+                .iter()
+                .map(|glob| compile_glob("--exclude", glob))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// 只保留至少 `unused_for` 未被修改或访问的条目
+    pub fn unused_for(mut self, unused_for: Option<Duration>) -> Self {
+        self.unused_for = unused_for;
+        self
+    }
+
+    /// 最近使用时间是否满足 `--unused-for`；时间未知的条目视为仍在使用
+    pub fn is_unused(&self, last_use: &LastUse) -> bool {
+        let Some(unused_for) = self.unused_for else {
+            return true;
+        };
+        last_use
+            .idle_for(SystemTime::now())
+            .is_some_and(|idle| idle >= unused_for)
+    }
+
+    /// 是否指定了 `--unused-for`
+    pub fn has_age_limit(&self) -> bool {
+        self.unused_for.is_some()
+    }
+
+    /// 名称是否通过过滤：未指定 `--include` 时包含全部，`--exclude` 优先
+    pub fn matches(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(name)))
+            && !self.exclude.iter().any(|p| p.matches(name))
+    }
+
+    /// 由多个部分组成的名称（如三元组与 profile）是否通过过滤：任一部分符合 `--include` 即包含，
+    /// 任一部分符合 `--exclude` 即排除
+    pub fn matches_parts(&self, parts: &[&str]) -> bool {
+        (self.include.is_empty()
+            || parts
+                .iter()
+                .any(|part| self.include.iter().any(|p| p.matches(part))))
+            && !parts
+                .iter()
+                .any(|part| self.exclude.iter().any(|p| p.matches(part)))
+    }
+}
+
+/// 终端颜色输出方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// 删除汇总的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// 输出相关的默认设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub color: ColorMode,
+    /// `--json` 优先
+    pub format: OutputFormat,
+}
+
+/// 扫描历史的设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// 每次 `status` 都记录一次快照，等同于总是传入 `--record`
+    pub record: bool,
+}
+
+/// 基于目录扫描的清理目标（Rust、Solana）的设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetConfig {
+    pub enabled: bool,
+    /// 扫描根目录，支持 `~/` 前缀；留空使用内置默认路径
+    pub root: PathBuf,
+    /// 清理时始终保留最近修改的 N 个版本，0 表示不保留
+    pub keep_latest: usize,
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        TargetConfig {
+            enabled: true,
+            root: PathBuf::new(),
+            keep_latest: 0,
+        }
+    }
+}
+
+/// 扫描根目录；未配置且无法确定主目录时返回错误
+fn configured_root(root: &Path) -> Result<PathBuf> {
+    if root.as_os_str().is_empty() {
+        return Err(CleanupError::MissingHome);
+    }
+    Ok(root.to_path_buf())
+}
+
+/// 按保留策略需要保留的路径：最近修改的 `keep_latest` 个
+pub(crate) fn retained<'a>(
+    keep_latest: usize,
+    paths: impl Iterator<Item = &'a Path>,
+) -> Vec<&'a Path> {
+    if keep_latest == 0 {
+        return Vec::new();
+    }
+    let mut paths: Vec<(&Path, SystemTime)> = paths
+        .map(|path| {
+            let modified = fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (path, modified)
+        })
+        .collect();
+    paths.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    paths
+        .into_iter()
+        .take(keep_latest)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+impl TargetConfig {
+    /// 扫描根目录
+    pub fn root(&self) -> Result<PathBuf> {
+        configured_root(&self.root)
+    }
+}
+
+/// 配置中声明的自定义清理目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTarget {
+    /// 目标名称，同时作为子命令名
+    pub name: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// 条目所在的目录，支持 `~/` 前缀
+    pub root: PathBuf,
+    /// 匹配条目名称的 glob
+    #[serde(default = "match_everything")]
+    pub pattern: String,
+    /// 探测条目版本的命令，`{path}` 会被替换为条目路径，输出的第一行作为版本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// 标记当前使用中条目的符号链接（相对 `root`，指向某个条目），
+    /// 或条目内部的标记文件名；使用中的条目不会被删除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub keep_latest: usize,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+fn match_everything() -> String {
+    "*".to_string()
+}
+
+impl CustomTarget {
+    /// 条目所在的目录
+    pub fn root(&self) -> Result<PathBuf> {
+        configured_root(&self.root)
+    }
+
+    /// 编译条目名称的 glob
+    pub fn pattern(&self) -> Result<glob::Pattern> {
+        compile_glob(&format!("custom target {}", self.name), &self.pattern)
+    }
+}
+
+/// iOS 模拟器的设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulatorConfig {
+    pub enabled: bool,
+    /// CoreSimulator 缓存与日志目录；留空使用内置默认路径
+    pub caches: Vec<PathBuf>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        SimulatorConfig {
+            enabled: true,
+            caches: Vec::new(),
+        }
+    }
+}
+
+/// sccache 本地磁盘缓存的设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SccacheConfig {
+    pub enabled: bool,
+    /// 缓存目录；留空时依次使用 `$SCCACHE_DIR` 与平台默认位置
+    pub root: PathBuf,
+}
+
+impl Default for SccacheConfig {
+    fn default() -> Self {
+        SccacheConfig {
+            enabled: true,
+            root: PathBuf::new(),
+        }
+    }
+}
+
+/// cargo target 目录的设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetDirConfig {
+    pub enabled: bool,
+    /// 查找 target 目录的根目录；命令行未给出路径且此处留空时使用当前目录
+    pub roots: Vec<PathBuf>,
+    /// 在根目录下查找的最大深度
+    pub depth: usize,
+}
+
+impl Default for TargetDirConfig {
+    fn default() -> Self {
+        TargetDirConfig {
+            enabled: true,
+            roots: Vec::new(),
+            depth: 4,
+        }
+    }
+}
+
+/// Solana 测试账本（`test-ledger`）的设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolanaLedgerConfig {
+    pub enabled: bool,
+    /// 查找账本的项目根目录；命令行未给出路径且此处留空时使用当前目录
+    pub roots: Vec<PathBuf>,
+    /// 在根目录下查找的最大深度
+    pub depth: usize,
+}
+
+impl Default for SolanaLedgerConfig {
+    fn default() -> Self {
+        SolanaLedgerConfig {
+            enabled: true,
+            roots: Vec::new(),
+            depth: 4,
+        }
+    }
+}
+
+/// `~/.config/cleanup/config.toml` 中的配置，启动时加载一次
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// 外部命令的默认超时（秒），`--timeout` 优先
+    pub timeout: u64,
+    /// 永远不会被提供删除的条目名称，支持 glob（如 `1.75.0-*`）
+    pub protect: Vec<String>,
+    pub output: OutputConfig,
+    pub history: HistoryConfig,
+    pub rust: TargetConfig,
+    pub solana: TargetConfig,
+    pub simulator: SimulatorConfig,
+    /// `cargo install` 安装的二进制，根目录默认 `~/.cargo`
+    pub cargo_bin: TargetConfig,
+    pub sccache: SccacheConfig,
+    pub target_dir: TargetDirConfig,
+    pub solana_ledger: SolanaLedgerConfig,
+    /// 自定义清理目标，以 `[[custom]]` 声明
+    pub custom: Vec<CustomTarget>,
+    /// 配置文件位置；为 `None` 时使用内置默认值
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// 本次命令的 `--include`/`--exclude` 过滤
+    #[serde(skip)]
+    pub filter: Filter,
+    /// 本次命令的 `--force`：删除正被进程使用的条目
+    #[serde(skip)]
+    pub force: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            timeout: 10,
+            protect: Vec::new(),
+            output: OutputConfig::default(),
+            history: HistoryConfig::default(),
+            rust: TargetConfig::default(),
+            solana: TargetConfig::default(),
+            simulator: SimulatorConfig::default(),
+            cargo_bin: TargetConfig::default(),
+            sccache: SccacheConfig::default(),
+            target_dir: TargetDirConfig::default(),
+            solana_ledger: SolanaLedgerConfig::default(),
+            custom: Vec::new(),
+            source: None,
+            filter: Filter::default(),
+            force: false,
+        }
+    }
+}
+
+impl Config {
+    /// 默认配置文件位置：`$XDG_CONFIG_HOME/cleanup/config.toml`，默认 `~/.config/cleanup`
+    fn default_path(env: &Env) -> Option<PathBuf> {
+        let base = match env.var("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => env.home().ok()?.join(".config"),
+        };
+        Some(base.join("cleanup/config.toml"))
+    }
+
+    /// 加载配置；`path` 为显式指定的文件，必须存在，默认位置的文件可以不存在
+    pub fn load(env: &Env, path: Option<&Path>) -> Result<Config> {
+        let (path, required) = match path {
+            Some(path) => (Some(path.to_path_buf()), true),
+            None => (Self::default_path(env), false),
+        };
+        let Some(path) = path else {
+            return Ok(Config::default());
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Config::default());
+            }
+            Err(err) => return Err(CleanupError::io(&path, err)),
+        };
+        let mut config = Self::parse(&text).map_err(|message| CleanupError::Parse {
+            what: format!("config {}", path.display()),
+            message,
+        })?;
+        config.validate(&path)?;
+        config.source = Some(path);
+        Ok(config)
+    }
+
+    /// 检查自定义目标的名称与 glob 以及保护列表
+    fn validate(&self, path: &Path) -> Result<()> {
+        for target in &self.custom {
+            if BUILTIN_COMMANDS.contains(&target.name.as_str()) {
+                return Err(CleanupError::Parse {
+                    what: format!("config {}", path.display()),
+                    message: format!(
+                        "custom target '{}' has the same name as a built-in subcommand",
+                        target.name
+                    ),
+                });
+            }
+            target.pattern()?;
+        }
+        for protected in &self.protect {
+            compile_glob("protect", protected)?;
+        }
+        Ok(())
+    }
+
+    fn parse(text: &str) -> std::result::Result<Config, String> {
+        toml::from_str(text).map_err(|err| err.to_string().trim().to_string())
+    }
+
+    /// 展开 `~/` 并为未配置的路径填入默认值
+    pub fn resolve(mut self, home: Option<&Path>) -> Config {
+        let expand = |path: &Path, default: &str| -> PathBuf {
+            if path.as_os_str().is_empty() {
+                return home.map(|home| home.join(default)).unwrap_or_default();
+            }
+            match (home, path.strip_prefix("~")) {
+                (Some(home), Ok(rest)) => home.join(rest),
+                _ => path.to_path_buf(),
+            }
+        };
+        self.rust.root = expand(&self.rust.root, ".rustup/toolchains");
+        self.solana.root = expand(&self.solana.root, ".local/share/solana/install");
+        self.cargo_bin.root = expand(&self.cargo_bin.root, ".cargo");
+        if !self.sccache.root.as_os_str().is_empty() {
+            self.sccache.root = expand(&self.sccache.root, "");
+        }
+        self.simulator.caches = if self.simulator.caches.is_empty() {
+            [
+                "Library/Developer/CoreSimulator/Caches",
+                "Library/Logs/CoreSimulator",
+            ]
+            .iter()
+            .filter_map(|dir| home.map(|home| home.join(dir)))
+            .collect()
+        } else {
+            self.simulator
+                .caches
+                .iter()
+                .map(|path| expand(path, ""))
+                .collect()
+        };
+        for root in &mut self.target_dir.roots {
+            *root = expand(root, "");
+        }
+        for root in &mut self.solana_ledger.roots {
+            *root = expand(root, "");
+        }
+        for target in &mut self.custom {
+            target.root = expand(&target.root, "");
+        }
+        self
+    }
+
+    /// 按名称查找自定义目标
+    pub fn custom_target(&self, name: &str) -> Option<&CustomTarget> {
+        self.custom.iter().find(|target| target.name == name)
+    }
+
+    /// 条目是否在保护列表中
+    pub fn is_protected(&self, name: &str) -> bool {
+        self.protect.iter().any(|protected| {
+            protected == name || glob::Pattern::new(protected).is_ok_and(|p| p.matches(name))
+        })
+    }
+
+    /// 受保护条目在列表中的标记
+    pub fn lock_marker(&self, name: &str) -> &'static str {
+        if self.is_protected(name) { " 🔒" } else { "" }
+    }
+
+    /// 从待删除列表中去掉受保护以及按保留策略保留的条目，并提示被保留的条目
+    pub fn removable<'a, T>(
+        &self,
+        keep_latest: usize,
+        items: Vec<&'a T>,
+        key: impl Fn(&T) -> (&str, &Path),
+    ) -> Vec<&'a T> {
+        let retained = retained(keep_latest, items.iter().map(|item| key(item).1));
+        let retained: Vec<PathBuf> = retained.into_iter().map(Path::to_path_buf).collect();
+        items
+            .into_iter()
+            .filter(|item| {
+                let (name, path) = key(item);
+                let reason = if self.is_protected(name) {
+                    "protected"
+                } else if retained.iter().any(|kept| kept == path) {
+                    "keep_latest"
+                } else {
+                    return true;
+                };
+                println!("🔒 Keeping {} ({})", name.blue(), reason);
+                false
+            })
+            .collect()
+    }
+
+    /// 按配置设置终端颜色
+    pub fn apply_output(&self) {
+        match self.output.color {
+            ColorMode::Auto => {}
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+
+    /// 输出当前生效的配置
+    pub fn show(env: &Env) -> Result<()> {
+        let config = env.settings();
+        match config.source.as_ref() {
+            Some(path) => println!("{} {}", "⚙ Config:".bold().cyan(), path.display()),
+            None => println!(
+                "{} {}",
+                "⚙ Config:".bold().cyan(),
+                "built-in defaults (no config file)".dimmed()
+            ),
+        }
+        println!();
+        let text = toml::to_string_pretty(config).map_err(|err| CleanupError::Parse {
+            what: "config".to_string(),
+            message: err.to_string(),
+        })?;
+        print!("{}", text);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fills_defaults_and_expands_home() {
+        let config = Config::parse(
+            r#"
+            timeout = 30
+            protect = ["1.75.0-x86_64-apple-darwin"]
+
+            [rust]
+            keep_latest = 2
+
+            [solana]
+            enabled = false
+            root = "~/solana"
+            "#,
+        )
+        .expect("valid config")
+        .resolve(Some(Path::new("/home/dev")));
+
+        assert_eq!(config.timeout, 30);
+        assert!(config.is_protected("1.75.0-x86_64-apple-darwin"));
+        assert!(!config.is_protected("1.75.1-x86_64-apple-darwin"));
+        assert!(config.rust.enabled);
+        assert_eq!(config.rust.keep_latest, 2);
+        assert_eq!(config.rust.root, Path::new("/home/dev/.rustup/toolchains"));
+        assert!(!config.solana.enabled);
+        assert_eq!(config.solana.root, Path::new("/home/dev/solana"));
+        assert_eq!(config.cargo_bin.root, Path::new("/home/dev/.cargo"));
+        assert_eq!(config.simulator.caches.len(), 2);
+        assert_eq!(config.output.color, ColorMode::Auto);
+
+        assert!(Config::parse("[rust]\nenable = true").is_err());
+
+        let custom = Config::parse(
+            r#"
+            [[custom]]
+            name = "weights"
+            root = "~/models"
+            active = "current"
+            "#,
+        )
+        .expect("valid custom target")
+        .resolve(Some(Path::new("/home/dev")));
+        let weights = custom.custom_target("weights").expect("declared target");
+        assert_eq!(weights.root, Path::new("/home/dev/models"));
+        assert!(weights.enabled);
+        assert!(weights.pattern().expect("default glob").matches("v1"));
+        assert!(custom.validate(Path::new("config.toml")).is_ok());
+
+        let shadowing = Config::parse("[[custom]]\nname = \"rust\"\nroot = \"~/rust\"")
+            .expect("parses before validation");
+        assert!(shadowing.validate(Path::new("config.toml")).is_err());
+    }
+
+    #[test]
+    fn filter_and_protect_globs() {
+        let filter = Filter::new(&["1.7*".to_string()], &["*-gnu".to_string()]).expect("globs");
+        assert!(filter.matches("1.75.0-aarch64-apple-darwin"));
+        assert!(!filter.matches("1.75.0-x86_64-unknown-linux-gnu"));
+        assert!(!filter.matches("stable-aarch64-apple-darwin"));
+        assert!(Filter::default().matches("anything"));
+        assert!(Filter::new(&["[".to_string()], &[]).is_err());
+
+        let config = Config {
+            protect: vec!["1.75.0-*".to_string()],
+            ..Config::default()
+        };
+        assert!(config.is_protected("1.75.0-aarch64-apple-darwin"));
+        assert_eq!(config.lock_marker("1.75.0-aarch64-apple-darwin"), " 🔒");
+        assert_eq!(config.lock_marker("1.76.0-aarch64-apple-darwin"), "");
+    }
+}
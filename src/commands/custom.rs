@@ -0,0 +1,344 @@
+use crate::commands::{
+    Audit, AuditEntry, Backend, Cache, CleanupError, Config, CustomTarget, DirSize, Env, LastUse,
+    Removal, Removals, Result, Runner, Scan, ScanEvent, Utils,
+};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+pub struct Custom;
+
+#[derive(Debug)]
+pub struct CustomInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: DirSize,
+    pub is_active: bool,
+    pub version: Option<String>,
+    pub last_use: LastUse,
+}
+
+impl Custom {
+    /// 把探测命令按空白拆成参数后再在每个参数中替换 `{path}`，路径中的空格不会拆开参数
+    fn probe_args(command: &str, item: &Path) -> Vec<String> {
+        let item = item.to_string_lossy();
+        command
+            .split_whitespace()
+            .map(|part| part.replace("{path}", &item))
+            .collect()
+    }
+
+    /// 执行目标配置的版本探测命令，取输出的第一行
+    fn get_version_info(env: &Env, target: &CustomTarget, item: &Path) -> Option<String> {
+        let args = Self::probe_args(target.version.as_ref()?, item);
+        let (program, args) = args.split_first()?;
+        let output = env.run(program, args, Runner::probe_timeout()).ok()?;
+        output
+            .stdout_string()
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(|line| line.to_string())
+    }
+
+    /// `active` 为指向某个条目的符号链接时，返回该条目的路径
+    fn get_active_link(root: &Path, target: &CustomTarget) -> Option<PathBuf> {
+        let link = root.join(target.active.as_ref()?);
+        let resolved = fs::read_link(&link).ok()?;
+        let resolved = if resolved.is_absolute() {
+            resolved
+        } else {
+            link.parent()?.join(resolved)
+        };
+        resolved.canonicalize().ok()
+    }
+
+    /// 条目是否正在使用：被 `active` 链接指向（或位于其中），或包含 `active` 标记文件
+    fn is_active(target: &CustomTarget, active_link: Option<&Path>, item: &Path) -> bool {
+        if let Some(active) = active_link {
+            return item
+                .canonicalize()
+                .is_ok_and(|item| active.starts_with(item));
+        }
+        target
+            .active
+            .as_ref()
+            .is_some_and(|marker| item.join(marker).exists())
+    }
+
+    /// 获取自定义目标的所有条目
+    pub fn get_items(env: &Env, target: &CustomTarget) -> Result<Vec<CustomInfo>> {
+        Self::scan_items(env, target, |_| {})
+    }
+
+    /// 扫描自定义目标中匹配 glob 的条目，每扫描完一个就调用 `on_item`
+    pub fn scan_items(
+        env: &Env,
+        target: &CustomTarget,
+        on_item: impl Fn(&CustomInfo) + Sync,
+    ) -> Result<Vec<CustomInfo>> {
+        let root = target.root()?;
+        let pattern = target.pattern()?;
+        if !root.exists() {
+            return Ok(Vec::new());
+        }
+        let active_link = Self::get_active_link(&root, target);
+
+        let entries = fs::read_dir(&root).map_err(|err| CleanupError::io(&root, err))?;
+        // `active` 链接本身不是条目
+        let marker = target.active.as_ref().map(|active| root.join(active));
+        let filter = &env.settings().filter;
+        let paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|e| e.path())
+            .filter(|path| Some(path) != marker.as_ref())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| pattern.matches(name) && filter.matches(name))
+            })
+            .collect();
+        Scan::emit(ScanEvent::Discovered(paths.len() as u64));
+
+        let items = paths
+            .into_par_iter()
+            .filter(|_| !Scan::is_cancelled())
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?.to_string();
+                let (size, last_use) = Cache::measure_dir(&path);
+                if !filter.is_unused(&last_use) {
+                    Scan::emit(ScanEvent::Completed);
+                    return None;
+                }
+                let is_active = Self::is_active(target, active_link.as_deref(), &path);
+                let version = Self::get_version_info(env, target, &path);
+                let info = CustomInfo {
+                    name,
+                    path,
+                    size,
+                    is_active,
+                    version,
+                    last_use,
+                };
+                Scan::emit(ScanEvent::Completed);
+                on_item(&info);
+                Some(info)
+            })
+            .collect();
+        Ok(items)
+    }
+
+    /// 格式化单个条目的列表行
+    fn format_row(config: &Config, item: &CustomInfo) -> String {
+        let status = if item.is_active {
+            "✓ active".green()
+        } else {
+            "  inactive".red()
+        };
+        let version_info = item
+            .version
+            .as_ref()
+            .map(|v| format!(" ({})", v))
+            .unwrap_or_default();
+        format!(
+            "{:<50} {:>10} {:>8} {}",
+            format!(
+                "{}{}{}",
+                item.name,
+                version_info,
+                config.lock_marker(&item.name)
+            ),
+            Utils::format_dir_size(&item.size).yellow(),
+            Utils::format_age(&item.last_use).dimmed(),
+            status
+        )
+    }
+
+    /// 列出自定义目标的条目
+    pub fn show_items(env: &Env, target: &CustomTarget) -> Result<()> {
+        println!("{}", format!("📦 {}:", target.name).bold().cyan());
+        println!();
+        if !target.enabled {
+            println!("{} is disabled in config.", target.name);
+            return Ok(());
+        }
+        Scan::begin(&target.name);
+        let items = Self::scan_items(env, target, |item| {
+            Scan::println(Self::format_row(env.settings(), item))
+        });
+        Scan::finish();
+        let items = items?;
+        if items.is_empty() {
+            println!("No {} items found.", target.name);
+            return Ok(());
+        }
+        let total_size: DirSize = items.iter().map(|item| item.size).sum();
+        println!();
+        println!(
+            "Total: {} items, {}",
+            items.len(),
+            Utils::format_dir_size(&total_size).bold()
+        );
+        Ok(())
+    }
+
+    /// 删除目标 `target` 中的一个条目，条目可以是目录或单个文件
+    pub fn remove_item(env: &Env, target: &str, item: &CustomInfo) -> Result<()> {
+        let entry = AuditEntry::new(target, &item.name, item.size.reclaimable).path(&item.path);
+        Audit::track(env, entry, || {
+            let result = if item.path.is_dir() && !item.path.is_symlink() {
+                fs::remove_dir_all(&item.path)
+            } else {
+                fs::remove_file(&item.path)
+            };
+            result.map_err(|err| CleanupError::io(&item.path, err))
+        })
+    }
+
+    /// 清理自定义目标中未使用的条目
+    pub fn clean_items(env: &Env, target: &CustomTarget) -> Result<()> {
+        let config = env.settings();
+        if !target.enabled {
+            println!("{} is disabled in config.", target.name);
+            return Ok(());
+        }
+        Scan::begin(&target.name);
+        let list = Self::get_items(env, target);
+        Scan::finish();
+        let list = list?;
+        let inactive: Vec<&CustomInfo> = list.iter().filter(|item| !item.is_active).collect();
+        if inactive.is_empty() {
+            println!("No {} items found.", target.name);
+            return Ok(());
+        }
+
+        println!("{}", format!("🧹 Cleaning {}:", target.name).bold().cyan());
+
+        let inactive = config.removable(target.keep_latest, inactive, |item| {
+            (item.name.as_str(), item.path.as_path())
+        });
+        if inactive.is_empty() {
+            println!("No removable {} items.", target.name);
+            return Ok(());
+        }
+
+        let items: Vec<String> = inactive
+            .iter()
+            .map(|item| format!("{} ({})", item.name, Utils::format_dir_size(&item.size)))
+            .collect();
+        let selections = env
+            .multi_select("Select items to remove:", &items)
+            .map_err(CleanupError::Prompt)?;
+
+        if selections.is_empty() {
+            println!("No {} items selected.", target.name);
+            return Ok(());
+        }
+
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let select = &inactive[index];
+                Removal::new(
+                    &target.name,
+                    &select.name,
+                    select.size.reclaimable,
+                    Some(&select.path),
+                    Backend::Filesystem,
+                    move || Self::remove_item(env, &target.name, select),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{Config, ScriptedRunner};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_items_matches_glob_and_follows_active_link() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_custom_test_{}", nanos));
+        let root = home.join("models");
+        for item in ["llama-7b", "llama-13b", "notes"] {
+            fs::create_dir_all(root.join(item)).expect("Failed to create item");
+            fs::write(root.join(item).join("weights.bin"), [0u8; 32])
+                .expect("Failed to write weights");
+        }
+        std::os::unix::fs::symlink(root.join("llama-13b"), root.join("current"))
+            .expect("Failed to link active item");
+
+        let config: Config = toml::from_str(
+            r#"
+            [[custom]]
+            name = "models"
+            root = "~/models"
+            pattern = "llama-*"
+            active = "current"
+            version = "cat {path}/VERSION"
+            "#,
+        )
+        .expect("valid config");
+        let env = Env::with_home(&home)
+            .runner(ScriptedRunner::new().respond("llama-7b/VERSION", "\n7b-q4\n"))
+            .config(config);
+        let target = env.settings().custom_target("models").expect("target");
+
+        let mut items = Custom::get_items(&env, target).expect("custom items");
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "llama-13b");
+        assert!(items[0].is_active);
+        assert_eq!(items[0].version, None);
+        assert!(!items[1].is_active);
+        assert_eq!(items[1].version.as_deref(), Some("7b-q4"));
+        assert_eq!(items[1].size.logical, 32);
+
+        fs::remove_dir_all(&home).expect("Failed to remove temp directory");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn version_probe_keeps_paths_with_spaces_in_one_argument() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_custom_space_test_{}", nanos));
+        let item = home.join("my models/llama 7b");
+        fs::create_dir_all(&item).expect("Failed to create item");
+        fs::write(item.join("VERSION"), "7b-q4\n").expect("Failed to write version");
+
+        assert_eq!(
+            Custom::probe_args("cat {path}/VERSION", &item),
+            ["cat".to_string(), format!("{}/VERSION", item.display())]
+        );
+        let config: Config = toml::from_str(
+            r#"
+            [[custom]]
+            name = "models"
+            root = "~/my models"
+            version = "cat {path}/VERSION"
+            "#,
+        )
+        .expect("valid config");
+        let env = Env::with_home(&home).config(config);
+        let target = env.settings().custom_target("models").expect("target");
+        assert_eq!(
+            Custom::get_version_info(&env, target, &item).as_deref(),
+            Some("7b-q4")
+        );
+        fs::remove_dir_all(&home).expect("Failed to remove temp directory");
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::commands::{CleanupError, Result};
+
+pub struct Disk;
+
+/// 一个文件系统卷的容量（字节）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    /// `st_dev`，用于判断两个路径是否在同一个卷上
+    pub device: u64,
+    /// 挂载点：与路径位于同一设备上的最上层祖先目录
+    pub mount: PathBuf,
+    pub total: u64,
+    pub used: u64,
+    /// 普通用户可用的空间（`f_bavail`），不含为 root 保留的部分
+    pub free: u64,
+}
+
+impl Volume {
+    /// 已用空间占总容量的百分比
+    pub fn used_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.used as f64 * 100.0 / self.total as f64
+    }
+
+    /// `size` 占该卷已用空间的百分比
+    pub fn share_of_used(&self, size: u64) -> f64 {
+        if self.used == 0 {
+            return 0.0;
+        }
+        size as f64 * 100.0 / self.used as f64
+    }
+}
+
+impl Disk {
+    /// 路径本身或最近的已存在祖先；扫描根目录不存在时仍能找到它所在的卷
+    fn existing_ancestor(path: &Path) -> Option<&Path> {
+        path.ancestors().find(|ancestor| ancestor.exists())
+    }
+
+    #[cfg(unix)]
+    fn device(path: &Path) -> io::Result<u64> {
+        use std::os::unix::fs::MetadataExt;
+        Ok(std::fs::metadata(path)?.dev())
+    }
+
+    /// 沿祖先目录向上，直到设备号改变
+    #[cfg(unix)]
+    fn mount_point(path: &Path, device: u64) -> PathBuf {
+        let mut mount = path;
+        while let Some(parent) = mount.parent() {
+            if Self::device(parent).ok() != Some(device) {
+                break;
+            }
+            mount = parent;
+        }
+        mount.to_path_buf()
+    }
+
+    /// 用 `statvfs` 读取 `path` 所在卷的容量
+    #[cfg(unix)]
+    pub fn volume(path: &Path) -> Result<Volume> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let path = Self::existing_ancestor(path).unwrap_or(path);
+        let path = path
+            .canonicalize()
+            .map_err(|err| CleanupError::io(path, err))?;
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|err| CleanupError::io(&path, io::Error::other(err)))?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `c_path` 是以 NUL 结尾的字符串，`stat` 由调用方分配
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return Err(CleanupError::io(&path, io::Error::last_os_error()));
+        }
+        let device = Self::device(&path).map_err(|err| CleanupError::io(&path, err))?;
+
+        // 各平台上这些字段的整数宽度不同
+        #[allow(clippy::unnecessary_cast)]
+        let (blocks, bfree, bavail, frsize) = (
+            stat.f_blocks as u64,
+            stat.f_bfree as u64,
+            stat.f_bavail as u64,
+            stat.f_frsize as u64,
+        );
+        Ok(Volume {
+            device,
+            mount: Self::mount_point(&path, device),
+            total: blocks * frsize,
+            used: blocks.saturating_sub(bfree) * frsize,
+            free: bavail * frsize,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn volume(path: &Path) -> Result<Volume> {
+        Err(CleanupError::io(
+            path,
+            io::Error::new(io::ErrorKind::Unsupported, "statvfs is not available"),
+        ))
+    }
+
+    /// 所有路径所在的卷，同一个卷只出现一次，顺序与路径一致
+    pub fn volumes<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Vec<Result<Volume>> {
+        let mut volumes: Vec<Result<Volume>> = Vec::new();
+        for path in paths {
+            let volume = Self::volume(path);
+            let seen = volumes.iter().any(|known| match (known, &volume) {
+                (Ok(known), Ok(volume)) => known.device == volume.device,
+                _ => false,
+            });
+            if !seen {
+                volumes.push(volume);
+            }
+        }
+        volumes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn volume_of_missing_path_uses_existing_ancestor() {
+        let temp = std::env::temp_dir();
+        let volume = Disk::volume(&temp.join("cleanup_disk_test/missing/dir")).expect("volume");
+
+        assert!(volume.total > 0);
+        assert!(volume.used <= volume.total);
+        assert!(volume.free <= volume.total);
+        assert!(
+            temp.canonicalize()
+                .expect("temp dir")
+                .starts_with(&volume.mount)
+        );
+        assert_eq!(Disk::volumes([temp.as_path(), temp.as_path()]).len(), 1);
+
+        let half = Volume {
+            used: 200,
+            ..volume
+        };
+        assert_eq!(half.share_of_used(50), 25.0);
+        assert!(half.used_percent() <= 100.0);
+        assert_eq!(Volume { used: 0, ..half }.share_of_used(50), 0.0);
+    }
+}
@@ -0,0 +1,260 @@
+use dialoguer::{Confirm, MultiSelect, theme::ColorfulTheme};
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    io,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::commands::{
+    CleanupError, Config, Utils,
+    runner::{CommandOutput, ProbeError, Runner},
+};
+
+/// 执行外部命令的抽象，便于在测试中替换 rustup、xcrun 等工具
+pub trait CommandRunner: Send + Sync {
+    fn run(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        timeout: Duration,
+    ) -> Result<CommandOutput, ProbeError>;
+
+    /// 执行删除命令；默认与使用删除超时的 `run` 相同
+    fn run_removal(&self, program: &OsStr, args: &[OsString]) -> Result<CommandOutput, ProbeError> {
+        self.run(program, args, Runner::REMOVAL_TIMEOUT)
+    }
+
+    /// 命令是否可用
+    fn exists(&self, program: &str) -> bool;
+}
+
+impl<T: CommandRunner> CommandRunner for Arc<T> {
+    fn run(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        timeout: Duration,
+    ) -> Result<CommandOutput, ProbeError> {
+        self.as_ref().run(program, args, timeout)
+    }
+
+    fn run_removal(&self, program: &OsStr, args: &[OsString]) -> Result<CommandOutput, ProbeError> {
+        self.as_ref().run_removal(program, args)
+    }
+
+    fn exists(&self, program: &str) -> bool {
+        self.as_ref().exists(program)
+    }
+}
+
+/// 交互式选择的抽象，便于在测试中预设用户的选择
+pub trait Prompter: Send + Sync {
+    fn multi_select(&self, prompt: &str, items: &[String]) -> io::Result<Vec<usize>>;
+
+    /// 多选，`defaults` 为各项初始是否勾选
+    fn multi_select_with_defaults(
+        &self,
+        prompt: &str,
+        items: &[String],
+        defaults: &[bool],
+    ) -> io::Result<Vec<usize>>;
+
+    fn confirm(&self, prompt: &str) -> io::Result<bool>;
+}
+
+/// 使用真实进程执行命令
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        timeout: Duration,
+    ) -> Result<CommandOutput, ProbeError> {
+        Runner::run(program, args, timeout)
+    }
+
+    fn run_removal(&self, program: &OsStr, args: &[OsString]) -> Result<CommandOutput, ProbeError> {
+        Runner::run_removal(program, args)
+    }
+
+    fn exists(&self, program: &str) -> bool {
+        Utils::command_exists(program)
+    }
+}
+
+/// 使用 dialoguer 在终端中交互
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn multi_select(&self, prompt: &str, items: &[String]) -> io::Result<Vec<usize>> {
+        MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items(items)
+            .interact()
+            .map_err(io::Error::other)
+    }
+
+    fn multi_select_with_defaults(
+        &self,
+        prompt: &str,
+        items: &[String],
+        defaults: &[bool],
+    ) -> io::Result<Vec<usize>> {
+        MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items(items)
+            .defaults(defaults)
+            .interact()
+            .map_err(io::Error::other)
+    }
+
+    fn confirm(&self, prompt: &str) -> io::Result<bool> {
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .interact()
+            .map_err(io::Error::other)
+    }
+}
+
+/// 运行环境：主目录、环境变量、配置、外部命令执行器与交互方式
+pub struct Env {
+    home: Option<PathBuf>,
+    /// `None` 表示读取当前进程的环境变量
+    vars: Option<HashMap<String, String>>,
+    config: Config,
+    runner: Box<dyn CommandRunner>,
+    prompter: Box<dyn Prompter>,
+}
+
+impl Env {
+    /// 基于当前进程的真实环境
+    pub fn system() -> Self {
+        Env {
+            home: std::env::var_os("HOME").map(PathBuf::from),
+            vars: None,
+            config: Config::default(),
+            runner: Box::new(SystemRunner),
+            prompter: Box::new(TerminalPrompter),
+        }
+        .config(Config::default())
+    }
+
+    /// 以指定目录为主目录的隔离环境，不继承进程的环境变量
+    pub fn with_home(home: impl Into<PathBuf>) -> Self {
+        Env {
+            home: Some(home.into()),
+            vars: Some(HashMap::new()),
+            config: Config::default(),
+            runner: Box::new(SystemRunner),
+            prompter: Box::new(TerminalPrompter),
+        }
+        .config(Config::default())
+    }
+
+    pub fn var_override(mut self, key: &str, value: &str) -> Self {
+        self.vars
+            .get_or_insert_with(HashMap::new)
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// 使用给定配置，其中的路径相对当前主目录展开
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config.resolve(self.home.as_deref());
+        self
+    }
+
+    pub fn runner(mut self, runner: impl CommandRunner + 'static) -> Self {
+        self.runner = Box::new(runner);
+        self
+    }
+
+    pub fn prompter(mut self, prompter: impl Prompter + 'static) -> Self {
+        self.prompter = Box::new(prompter);
+        self
+    }
+
+    /// 用户主目录
+    pub fn home(&self) -> crate::commands::Result<PathBuf> {
+        self.home.clone().ok_or(CleanupError::MissingHome)
+    }
+
+    /// 持久数据（历史、审计日志）所在目录：`$XDG_DATA_HOME/cleanup`，默认 `~/.local/share/cleanup`
+    pub fn data_dir(&self) -> crate::commands::Result<PathBuf> {
+        let base = match self.var("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => self.home()?.join(".local/share"),
+        };
+        Ok(base.join("cleanup"))
+    }
+
+    /// 当前生效的配置
+    pub fn settings(&self) -> &Config {
+        &self.config
+    }
+
+    /// 读取环境变量
+    pub fn var(&self, key: &str) -> Option<String> {
+        match &self.vars {
+            Some(vars) => vars.get(key).cloned(),
+            None => std::env::var(key).ok(),
+        }
+    }
+
+    /// 执行外部命令
+    pub fn run<I, S>(
+        &self,
+        program: impl AsRef<OsStr>,
+        args: I,
+        timeout: Duration,
+    ) -> Result<CommandOutput, ProbeError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(|a| a.as_ref().to_owned()).collect();
+        self.runner.run(program.as_ref(), &args, timeout)
+    }
+
+    /// 执行不会被 Ctrl-C 中断的删除命令
+    pub fn run_removal<I, S>(
+        &self,
+        program: impl AsRef<OsStr>,
+        args: I,
+    ) -> Result<CommandOutput, ProbeError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(|a| a.as_ref().to_owned()).collect();
+        self.runner.run_removal(program.as_ref(), &args)
+    }
+
+    /// 命令是否可用
+    pub fn command_exists(&self, program: &str) -> bool {
+        self.runner.exists(program)
+    }
+
+    pub fn multi_select(&self, prompt: &str, items: &[String]) -> io::Result<Vec<usize>> {
+        self.prompter.multi_select(prompt, items)
+    }
+
+    pub fn multi_select_with_defaults(
+        &self,
+        prompt: &str,
+        items: &[String],
+        defaults: &[bool],
+    ) -> io::Result<Vec<usize>> {
+        self.prompter
+            .multi_select_with_defaults(prompt, items, defaults)
+    }
+
+    pub fn confirm(&self, prompt: &str) -> io::Result<bool> {
+        self.prompter.confirm(prompt)
+    }
+}
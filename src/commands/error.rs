@@ -0,0 +1,181 @@
+use colored::Colorize;
+use std::{fmt, io, path::PathBuf, sync::Mutex};
+
+use crate::commands::{ProbeError, Process, Processes};
+
+/// 全局统一的错误类型
+#[derive(Debug)]
+pub enum CleanupError {
+    /// 无法确定用户主目录
+    MissingHome,
+    /// 依赖的外部工具不存在
+    MissingTool(String),
+    /// 没有访问某个路径的权限
+    PermissionDenied { path: PathBuf, source: io::Error },
+    /// 读写文件系统失败
+    Io { path: PathBuf, source: io::Error },
+    /// 外部命令或文件的输出无法解析
+    Parse { what: String, message: String },
+    /// 外部命令执行失败
+    Command { command: String, source: ProbeError },
+    /// 读取用户输入失败
+    Prompt(io::Error),
+    /// 条目正被运行中的进程使用，未加 `--force` 时拒绝删除
+    InUse {
+        path: PathBuf,
+        processes: Vec<Process>,
+    },
+    /// 部分条目删除失败
+    PartialDeletion {
+        attempted: usize,
+        failures: Vec<(String, String)>,
+    },
+    /// 用户按下 Ctrl-C 取消
+    Cancelled,
+}
+
+pub type Result<T> = std::result::Result<T, CleanupError>;
+
+impl CleanupError {
+    /// 将 IO 错误按类型归类为权限错误或一般 IO 错误
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => CleanupError::PermissionDenied { path, source },
+            _ => CleanupError::Io { path, source },
+        }
+    }
+
+    pub fn command(command: &str, source: ProbeError) -> Self {
+        match source {
+            ProbeError::NotFound => CleanupError::MissingTool(
+                command
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or(command)
+                    .to_string(),
+            ),
+            ProbeError::Cancelled => CleanupError::Cancelled,
+            source => CleanupError::Command {
+                command: command.to_string(),
+                source,
+            },
+        }
+    }
+
+    /// 进程退出码
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CleanupError::Io { .. } | CleanupError::Prompt(_) => 1,
+            CleanupError::MissingHome => 2,
+            CleanupError::MissingTool(_) => 3,
+            CleanupError::PermissionDenied { .. } => 4,
+            CleanupError::Parse { .. } => 5,
+            CleanupError::Command { .. } => 6,
+            CleanupError::PartialDeletion { .. } => 7,
+            CleanupError::InUse { .. } => 8,
+            CleanupError::Cancelled => 130,
+        }
+    }
+}
+
+impl fmt::Display for CleanupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CleanupError::MissingHome => write!(f, "HOME environment variable is not set"),
+            CleanupError::MissingTool(tool) => write!(f, "{} not found in PATH", tool),
+            CleanupError::PermissionDenied { path, .. } => {
+                write!(f, "permission denied: {}", path.display())
+            }
+            CleanupError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            CleanupError::Parse { what, message } => {
+                write!(f, "failed to parse {}: {}", what, message)
+            }
+            CleanupError::Command { command, source } => write!(f, "{}: {}", command, source),
+            CleanupError::Prompt(err) => write!(f, "failed to read input: {}", err),
+            CleanupError::InUse { path, processes } => write!(
+                f,
+                "{} is in use by {}; pass --force to remove it anyway",
+                path.display(),
+                Processes::describe(processes)
+            ),
+            CleanupError::PartialDeletion {
+                attempted,
+                failures,
+            } => {
+                write!(
+                    f,
+                    "failed to remove {} of {} items",
+                    failures.len(),
+                    attempted
+                )?;
+                for (item, reason) in failures {
+                    write!(f, "\n    {}: {}", item, reason)?;
+                }
+                Ok(())
+            }
+            CleanupError::Cancelled => write!(f, "cancelled by user"),
+        }
+    }
+}
+
+impl std::error::Error for CleanupError {}
+
+static FAILURES: Mutex<Vec<CleanupError>> = Mutex::new(Vec::new());
+
+/// 收集一次运行中的所有失败，并在结束时输出汇总
+pub struct Report;
+
+impl Report {
+    /// 记录一个不中断流程的失败
+    pub fn record(err: CleanupError) {
+        if let Ok(mut failures) = FAILURES.lock() {
+            failures.push(err);
+        }
+    }
+
+    /// 输出失败汇总并返回进程退出码；第一个失败决定退出码
+    pub fn finish() -> i32 {
+        let Ok(mut failures) = FAILURES.lock() else {
+            return 1;
+        };
+        if failures.is_empty() {
+            return 0;
+        }
+
+        eprintln!();
+        eprintln!(
+            "{}",
+            format!("✗ {} problem(s) during this run:", failures.len())
+                .red()
+                .bold()
+        );
+        for failure in failures.iter() {
+            eprintln!("  - {}", failure);
+        }
+        let code = failures[0].exit_code();
+        failures.clear();
+        code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_are_classified_by_kind() {
+        let denied = CleanupError::io(
+            "/root",
+            io::Error::new(io::ErrorKind::PermissionDenied, "denied"),
+        );
+        assert_eq!(denied.exit_code(), 4);
+
+        let missing = CleanupError::io("/nope", io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!(missing.exit_code(), 1);
+
+        let tool = CleanupError::command("xcrun simctl list", ProbeError::NotFound);
+        assert!(matches!(tool, CleanupError::MissingTool(ref t) if t == "xcrun"));
+        assert_eq!(tool.exit_code(), 3);
+    }
+}
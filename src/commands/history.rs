@@ -0,0 +1,347 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::commands::{CleanupError, DirSize, Env, Result, Utils};
+
+/// 单个类别在某次快照中的占用（磁盘分配大小，字节）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategorySnapshot {
+    pub size: u64,
+    pub items: BTreeMap<String, u64>,
+}
+
+/// 一次 `status` 扫描的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Unix 秒
+    pub timestamp: u64,
+    pub categories: BTreeMap<String, CategorySnapshot>,
+}
+
+impl Snapshot {
+    pub fn new(timestamp: u64) -> Self {
+        Snapshot {
+            timestamp,
+            categories: BTreeMap::new(),
+        }
+    }
+
+    /// 当前时间的空快照
+    pub fn now() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self::new(now)
+    }
+
+    /// 记录一个类别的条目；同名条目的大小累加
+    pub fn record<'a>(
+        &mut self,
+        category: &str,
+        items: impl IntoIterator<Item = (&'a str, DirSize)>,
+    ) {
+        let entry = self.categories.entry(category.to_string()).or_default();
+        for (name, size) in items {
+            entry.size += size.allocated;
+            *entry.items.entry(name.to_string()).or_default() += size.allocated;
+        }
+    }
+
+    fn size(&self, category: &str) -> u64 {
+        self.categories.get(category).map_or(0, |c| c.size)
+    }
+
+    fn total(&self) -> u64 {
+        self.categories.values().map(|c| c.size).sum()
+    }
+}
+
+/// 两次快照之间某个类别或条目的变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Change {
+    category: String,
+    /// 为 `None` 时表示整个类别
+    item: Option<String>,
+    before: u64,
+    after: u64,
+}
+
+impl Change {
+    fn delta(&self) -> i128 {
+        self.after as i128 - self.before as i128
+    }
+
+    fn label(&self) -> String {
+        match &self.item {
+            Some(item) => format!("{} / {}", self.category, item),
+            None => self.category.clone(),
+        }
+    }
+}
+
+/// 按时间记录的扫描历史，用于观察各类别的增长趋势
+pub struct History;
+
+impl History {
+    /// 历史文件位置：数据目录下的 `history.jsonl`
+    fn history_path(env: &Env) -> Result<PathBuf> {
+        Ok(env.data_dir()?.join("history.jsonl"))
+    }
+
+    /// 追加一条快照，每行一个 JSON 对象
+    pub fn append(env: &Env, snapshot: &Snapshot) -> Result<()> {
+        Utils::append_json_line(&Self::history_path(env)?, snapshot)
+    }
+
+    /// 按记录顺序读取所有快照；文件不存在时为空
+    pub fn load(env: &Env) -> Result<Vec<Snapshot>> {
+        Utils::read_json_lines(&Self::history_path(env)?)
+    }
+
+    /// 两次快照之间所有类别与条目的变化，增长最多的在前；没有变化的不列出
+    fn changes(before: &Snapshot, after: &Snapshot) -> Vec<Change> {
+        let empty = CategorySnapshot::default();
+        let categories: BTreeSet<&String> = before
+            .categories
+            .keys()
+            .chain(after.categories.keys())
+            .collect();
+        let mut changes = Vec::new();
+        for category in categories {
+            let old = before.categories.get(category).unwrap_or(&empty);
+            let new = after.categories.get(category).unwrap_or(&empty);
+            changes.push(Change {
+                category: category.clone(),
+                item: None,
+                before: old.size,
+                after: new.size,
+            });
+            let items: BTreeSet<&String> = old.items.keys().chain(new.items.keys()).collect();
+            changes.extend(items.into_iter().map(|item| Change {
+                category: category.clone(),
+                item: Some(item.clone()),
+                before: old.items.get(item).copied().unwrap_or(0),
+                after: new.items.get(item).copied().unwrap_or(0),
+            }));
+        }
+        changes.retain(|change| change.delta() != 0);
+        changes.sort_by_key(|change| std::cmp::Reverse(change.delta()));
+        changes
+    }
+
+    /// 以 `+1.20 GB` / `-300.00 MB` 的形式展示变化量
+    fn format_delta(delta: i128) -> String {
+        let size = Utils::format_size(delta.unsigned_abs() as u64);
+        if delta > 0 {
+            format!("+{}", size).red().to_string()
+        } else if delta < 0 {
+            format!("-{}", size).green().to_string()
+        } else {
+            format!(" {}", size).dimmed().to_string()
+        }
+    }
+
+    fn print_change(change: &Change) {
+        println!(
+            "   {:<50} {:>12} → {:<12} {}",
+            change.label(),
+            Utils::format_size(change.before),
+            Utils::format_size(change.after),
+            Self::format_delta(change.delta())
+        );
+    }
+
+    fn print_empty_hint() {
+        println!("No history recorded yet. Run `cleanup status --record` to take a snapshot.");
+    }
+
+    /// 最近 `limit` 次快照中各类别的占用，以及首尾之间的变化
+    pub fn show_trends(env: &Env, limit: usize) -> Result<()> {
+        println!("{}", "📈 Storage Trends:".bold().cyan());
+        println!();
+        let snapshots = Self::load(env)?;
+        if snapshots.is_empty() {
+            Self::print_empty_hint();
+            return Ok(());
+        }
+        let start = snapshots.len().saturating_sub(limit.max(1));
+        let shown = &snapshots[start..];
+        let categories: BTreeSet<&String> =
+            shown.iter().flat_map(|s| s.categories.keys()).collect();
+
+        let header: String = categories
+            .iter()
+            .map(|category| format!(" {:>12}", category))
+            .collect();
+        println!("{:>4}  {:<16}{} {:>12}", "#", "Date", header, "Total");
+        for (index, snapshot) in shown.iter().enumerate() {
+            let sizes: String = categories
+                .iter()
+                .map(|category| format!(" {:>12}", Utils::format_size(snapshot.size(category))))
+                .collect();
+            println!(
+                "{:>4}  {:<16}{} {:>12}",
+                start + index + 1,
+                Utils::format_date(snapshot.timestamp),
+                sizes,
+                Utils::format_size(snapshot.total()).bold()
+            );
+        }
+
+        if let [first, .., last] = shown {
+            println!();
+            println!(
+                "Change since {}:",
+                Utils::format_date(first.timestamp).bold()
+            );
+            for category in &categories {
+                Self::print_change(&Change {
+                    category: category.to_string(),
+                    item: None,
+                    before: first.size(category),
+                    after: last.size(category),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 从 `since` 之后的第一次快照到最新一次之间增长最多的条目
+    pub fn show_growers(env: &Env, since: u64, limit: usize) -> Result<()> {
+        println!(
+            "{}",
+            format!("📈 Largest growers since {}:", Utils::format_date(since))
+                .bold()
+                .cyan()
+        );
+        println!();
+        let snapshots = Self::load(env)?;
+        let (Some(first), Some(last)) = (
+            snapshots.iter().find(|s| s.timestamp >= since),
+            snapshots.last(),
+        ) else {
+            Self::print_empty_hint();
+            return Ok(());
+        };
+        if first == last {
+            println!("Only one snapshot since then; nothing to compare.");
+            return Ok(());
+        }
+
+        println!(
+            "Comparing {} with {}",
+            Utils::format_date(first.timestamp),
+            Utils::format_date(last.timestamp)
+        );
+        let growers: Vec<Change> = Self::changes(first, last)
+            .into_iter()
+            .filter(|change| change.item.is_some() && change.delta() > 0)
+            .take(limit)
+            .collect();
+        if growers.is_empty() {
+            println!("Nothing has grown.");
+        }
+        growers.iter().for_each(Self::print_change);
+        Ok(())
+    }
+
+    /// 两次快照（按 `history` 中的编号）之间所有类别与条目的变化
+    pub fn show_diff(env: &Env, from: usize, to: usize) -> Result<()> {
+        let snapshots = Self::load(env)?;
+        let pick = |number: usize| {
+            number
+                .checked_sub(1)
+                .and_then(|index| snapshots.get(index))
+                .ok_or_else(|| CleanupError::Parse {
+                    what: "--diff".to_string(),
+                    message: format!(
+                        "no snapshot #{} (history has {} snapshots)",
+                        number,
+                        snapshots.len()
+                    ),
+                })
+        };
+        let (before, after) = (pick(from)?, pick(to)?);
+
+        println!(
+            "{}",
+            format!(
+                "📈 Changes from #{} ({}) to #{} ({}):",
+                from,
+                Utils::format_date(before.timestamp),
+                to,
+                Utils::format_date(after.timestamp)
+            )
+            .bold()
+            .cyan()
+        );
+        println!();
+        let changes = Self::changes(before, after);
+        if changes.is_empty() {
+            println!("No changes.");
+        }
+        changes.iter().for_each(Self::print_change);
+        println!();
+        println!(
+            "{} Total: {}",
+            "💾".bold(),
+            Self::format_delta(after.total() as i128 - before.total() as i128)
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp: u64, items: &[(&str, &str, u64)]) -> Snapshot {
+        let mut snapshot = Snapshot::new(timestamp);
+        for (category, item, size) in items {
+            snapshot.record(category, [(*item, DirSize::from_logical(*size))]);
+        }
+        snapshot
+    }
+
+    #[test]
+    fn changes_rank_growth_and_include_added_and_removed_items() {
+        let before = snapshot(
+            1,
+            &[
+                ("Rust", "stable", 100),
+                ("Rust", "nightly", 50),
+                ("Solana", "1.18", 70),
+            ],
+        );
+        let after = snapshot(
+            2,
+            &[
+                ("Rust", "stable", 100),
+                ("Rust", "beta", 80),
+                ("Solana", "1.18", 10),
+            ],
+        );
+
+        let changes = History::changes(&before, &after);
+        let summary: Vec<(String, i128)> = changes
+            .iter()
+            .map(|change| (change.label(), change.delta()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Rust / beta".to_string(), 80),
+                ("Rust".to_string(), 30),
+                ("Rust / nightly".to_string(), -50),
+                ("Solana".to_string(), -60),
+                ("Solana / 1.18".to_string(), -60),
+            ]
+        );
+    }
+}
@@ -1,61 +1,175 @@
-use crate::commands::{Rust, Simulator, Solana, Status};
+use crate::commands::{
+    CleanupError, Env, Reclaim, Result, Scan, Utils,
+    reclaim::{Action, Candidate},
+};
 use colored::Colorize;
-use dialoguer::{Confirm, theme::ColorfulTheme};
+use std::{
+    cmp::Reverse,
+    time::{Duration, SystemTime},
+};
 
+/// 跨所有类别的交互式清理向导：一次勾选、一次确认、一次执行
 pub struct Interactive;
 
-/// 清理 Rust、Solana 和 Simulator 的交互式向导
-#[allow(dead_code)]
+/// 超过这么久未使用的条目默认勾选
+const RECOMMENDED_IDLE: Duration = Duration::from_secs(30 * 86_400);
+
 impl Interactive {
-    fn prompt(prompt: &str) -> bool {
-        match Confirm::with_theme(&ColorfulTheme::default())
-            .with_prompt(prompt)
-            .interact()
-        {
-            Ok(choice) => choice,
-            Err(err) => {
-                eprintln!("Failed to read input: {}", err);
-                false
-            }
+    /// 默认勾选可再生的缓存、已不可用的模拟器，以及长期未使用且未被引用的条目；
+    /// 清空 sccache 会让之后的构建全部重新编译，只在整个缓存长期未使用时勾选
+    fn is_recommended(candidate: &Candidate, now: SystemTime) -> bool {
+        let idle = || {
+            candidate
+                .last_use
+                .idle_for(now)
+                .is_some_and(|idle| idle >= RECOMMENDED_IDLE)
+        };
+        match candidate.safety {
+            0 if matches!(candidate.action, Action::Sccache(_)) => idle(),
+            0 | 1 => true,
+            2 => idle(),
+            _ => false,
         }
     }
 
-    fn cleanup_rust() {
-        let rust_cleanup = Self::prompt("Clean up Rust toolchains?");
-
-        if rust_cleanup {
-            Rust::clean_rust_versions();
-            println!();
-        }
+    fn format_row(candidate: &Candidate) -> String {
+        let note = match candidate.safety {
+            0 => " (cache)",
+            1 => " (unavailable)",
+            3 => " (available device)",
+            4 => " (used by devices)",
+            _ => "",
+        };
+        format!(
+            "{:<12} {:<45} {:>10} {:>8}{}",
+            candidate.category,
+            candidate.name,
+            Utils::format_size(candidate.size.reclaimable),
+            Utils::format_age(&candidate.last_use),
+            note
+        )
     }
-    fn cleanup_solana() {
-        let solana_cleanup = Self::prompt("Clean up Solana installations?");
 
-        if solana_cleanup {
-            Solana::clean_solana_versions();
-            println!();
+    /// 按类别出现的顺序分组，组内大的在前
+    fn sort(candidates: &mut [Candidate]) {
+        let mut categories: Vec<String> = Vec::new();
+        for candidate in candidates.iter() {
+            if !categories.contains(&candidate.category) {
+                categories.push(candidate.category.clone());
+            }
         }
+        candidates.sort_by_key(|candidate| {
+            (
+                categories.iter().position(|c| *c == candidate.category),
+                Reverse(candidate.size.reclaimable),
+            )
+        });
     }
-    fn cleanup_simulator() {
-        let simulator_cleanup = Self::prompt("Clean up Simulator installations?");
 
-        if simulator_cleanup {
-            Simulator::clean_simulators();
-            println!();
-        }
-    }
-    pub fn run_wizard() {
+    pub fn run_wizard(env: &Env) -> Result<()> {
         println!("{}", "🎯 Interactive Cleanup Wizard".bold().cyan());
         println!();
 
-        Status::show_status();
+        env.home()?;
+        Scan::begin("Scanning");
+        let mut candidates = Reclaim::candidates(env);
+        Scan::finish();
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        if candidates.is_empty() {
+            println!("Nothing to clean up.");
+            return Ok(());
+        }
+        Self::sort(&mut candidates);
+
+        let total: u64 = candidates.iter().map(|c| c.size.reclaimable).sum();
+        println!(
+            "{} removable items, {} reclaimable",
+            candidates.len(),
+            Utils::format_size(total).bold().green()
+        );
+        println!();
+
+        let now = SystemTime::now();
+        let items: Vec<String> = candidates.iter().map(Self::format_row).collect();
+        let defaults: Vec<bool> = candidates
+            .iter()
+            .map(|candidate| Self::is_recommended(candidate, now))
+            .collect();
+        let selections = env
+            .multi_select_with_defaults(
+                "Select items to remove (recommended items are pre-selected):",
+                &items,
+                &defaults,
+            )
+            .map_err(CleanupError::Prompt)?;
+        if selections.is_empty() {
+            println!("Nothing selected.");
+            return Ok(());
+        }
+
+        let plan: Vec<&Candidate> = selections.iter().map(|&i| &candidates[i]).collect();
+        let planned: u64 = plan.iter().map(|c| c.size.reclaimable).sum();
         println!();
+        println!("{}", "📋 Cleanup plan:".bold().cyan());
+        for candidate in &plan {
+            println!("   {}", Self::format_row(candidate));
+        }
+        println!();
+        println!(
+            "Total: {} items, {} reclaimable",
+            plan.len(),
+            Utils::format_size(planned).bold().green()
+        );
+
+        let prompt = format!(
+            "Remove {} items to free {}?",
+            plan.len(),
+            Utils::format_size(planned)
+        );
+        if !env.confirm(&prompt).map_err(CleanupError::Prompt)? {
+            println!("Nothing removed.");
+            return Ok(());
+        }
+
+        let result = Reclaim::execute(env, &plan).finish(env);
+        if result.is_ok() {
+            println!();
+            println!("{}", "🎉 Cleanup completed!".bold().green());
+        }
+        result
+    }
+}
 
-        Self::cleanup_rust();
-        Self::cleanup_solana();
-        Self::cleanup_simulator();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{DirSize, LastUse};
+    use std::time::UNIX_EPOCH;
 
-        println!("{}", "🎉 Cleanup completed!".bold().green());
-        Status::show_status();
+    #[test]
+    fn sccache_is_preselected_only_when_idle() {
+        let now = SystemTime::now();
+        let days_ago = |days: u64| {
+            (now - Duration::from_secs(days * 86_400))
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs()
+        };
+        let sccache = |days: u64| Candidate {
+            category: "sccache".to_string(),
+            name: "cache (1 entries)".to_string(),
+            size: DirSize::from_logical(1024),
+            last_use: LastUse {
+                modified: days_ago(days),
+                accessed: 0,
+            },
+            safety: 0,
+            lock: None,
+            action: Action::Sccache(Vec::new()),
+        };
+        assert!(!Interactive::is_recommended(&sccache(1), now));
+        assert!(Interactive::is_recommended(&sccache(60), now));
     }
 }
@@ -1,13 +1,56 @@
+pub mod audit;
+pub mod cache;
+pub mod cargo_bin;
+pub mod config;
+pub mod custom;
+pub mod disk;
+pub mod env;
+pub mod error;
+pub mod history;
 pub mod interactive;
+pub mod processes;
+pub mod reclaim;
+pub mod removal;
+pub mod runner;
 pub mod rust;
+pub mod scan;
+pub mod sccache;
+#[cfg(any(test, feature = "test-support"))]
+pub mod scripted;
 pub mod simulator;
 pub mod solana;
+pub mod solana_ledger;
 pub mod status;
+pub mod target_dir;
+pub mod tui;
 pub mod utils;
 
+pub use audit::{Audit, AuditEntry, AuditQuery, Outcome};
+pub use cache::Cache;
+pub use cargo_bin::CargoBin;
+pub use config::{
+    Config, CustomTarget, Filter, OutputFormat, SccacheConfig, SolanaLedgerConfig, TargetConfig,
+    TargetDirConfig,
+};
+pub use custom::Custom;
+pub use disk::{Disk, Volume};
+pub use env::{CommandRunner, Env, Prompter};
+pub use error::{CleanupError, Report, Result};
+pub use history::{History, Snapshot};
 pub use interactive::Interactive;
+pub use processes::{Process, Processes};
+pub use reclaim::Reclaim;
+pub use removal::{Backend, Removal, RemovalResult, Removals};
+pub use runner::{CommandOutput, ProbeError, Runner};
 pub use rust::Rust;
+pub use scan::{Scan, ScanEvent};
+pub use sccache::{Sccache, TrimLimits};
+#[cfg(any(test, feature = "test-support"))]
+pub use scripted::{ScriptedPrompter, ScriptedRunner};
 pub use simulator::Simulator;
 pub use solana::Solana;
+pub use solana_ledger::SolanaLedger;
 pub use status::Status;
-pub use utils::Utils;
+pub use target_dir::TargetDir;
+pub use tui::Tui;
+pub use utils::{DirSize, LastUse, Utils};
@@ -0,0 +1,230 @@
+use colored::Colorize;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::commands::{Env, Runner};
+
+/// 一个正在运行的进程
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+}
+
+impl fmt::Display for Process {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pid {})", self.name, self.pid)
+    }
+}
+
+/// 某一时刻各进程正在使用的文件：可执行文件、工作目录与打开的文件
+#[derive(Debug, Default)]
+pub struct Processes {
+    entries: Vec<(Process, Vec<PathBuf>)>,
+}
+
+impl Processes {
+    /// 读取当前所有进程使用的文件；Linux 上读取 `/proc`，其它系统在有 `lsof` 时使用它，
+    /// 都不可用时为空
+    pub fn snapshot(env: &Env) -> Self {
+        if cfg!(target_os = "linux") {
+            let processes = Self::from_proc(Path::new("/proc"));
+            if !processes.entries.is_empty() {
+                return processes;
+            }
+        }
+        if !env.command_exists("lsof") {
+            return Self::default();
+        }
+        env.run(
+            "lsof",
+            ["-n", "-P", "-w", "-F", "pcn"],
+            Runner::probe_timeout(),
+        )
+        .map(|output| Self::parse_lsof(&output.stdout_string()))
+        .unwrap_or_default()
+    }
+
+    /// 从 `/proc/<pid>/{exe,cwd,fd/*}` 读取；无权限读取的进程与条目被跳过
+    fn from_proc(root: &Path) -> Self {
+        let own = std::process::id();
+        let Ok(dirs) = fs::read_dir(root) else {
+            return Self::default();
+        };
+        let entries = dirs
+            .flatten()
+            .filter_map(|dir| {
+                let pid: u32 = dir.file_name().to_str()?.parse().ok()?;
+                if pid == own {
+                    return None;
+                }
+                let dir = dir.path();
+                let name = fs::read_to_string(dir.join("comm"))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_default();
+                let mut paths: Vec<PathBuf> = ["exe", "cwd"]
+                    .iter()
+                    .filter_map(|link| fs::read_link(dir.join(link)).ok())
+                    .collect();
+                if let Ok(fds) = fs::read_dir(dir.join("fd")) {
+                    paths.extend(fds.flatten().filter_map(|fd| fs::read_link(fd.path()).ok()));
+                }
+                let paths: Vec<PathBuf> = paths
+                    .into_iter()
+                    .filter(|path| path.is_absolute())
+                    .map(Self::strip_deleted)
+                    .collect();
+                (!paths.is_empty()).then_some((Process { pid, name }, paths))
+            })
+            .collect();
+        Processes { entries }
+    }
+
+    /// 已删除但仍被打开的文件在 `/proc` 中带有 ` (deleted)` 后缀
+    fn strip_deleted(path: PathBuf) -> PathBuf {
+        match path.to_str().and_then(|s| s.strip_suffix(" (deleted)")) {
+            Some(stripped) => PathBuf::from(stripped),
+            None => path,
+        }
+    }
+
+    /// 解析 `lsof -F pcn` 的输出：`p<pid>` 开始一个进程，`c<命令>`、`n<文件>` 属于该进程
+    fn parse_lsof(output: &str) -> Self {
+        let own = std::process::id();
+        let mut entries: Vec<(Process, Vec<PathBuf>)> = Vec::new();
+        for line in output.lines() {
+            let Some((field, value)) = line.split_at_checked(1) else {
+                continue;
+            };
+            match field {
+                "p" => entries.push((
+                    Process {
+                        pid: value.parse().unwrap_or(0),
+                        name: String::new(),
+                    },
+                    Vec::new(),
+                )),
+                "c" => {
+                    if let Some((process, _)) = entries.last_mut() {
+                        process.name = value.to_string();
+                    }
+                }
+                "n" if value.starts_with('/') => {
+                    if let Some((_, paths)) = entries.last_mut() {
+                        paths.push(PathBuf::from(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+        entries
+            .retain(|(process, paths)| process.pid != 0 && process.pid != own && !paths.is_empty());
+        Processes { entries }
+    }
+
+    /// 使用 `path` 或其下任何文件的进程
+    pub fn users(&self, path: &Path) -> Vec<Process> {
+        let canonical = fs::canonicalize(path).ok();
+        self.entries
+            .iter()
+            .filter(|(_, paths)| {
+                paths.iter().any(|used| {
+                    used.starts_with(path)
+                        || canonical.as_deref().is_some_and(|c| used.starts_with(c))
+                })
+            })
+            .map(|(process, _)| process.clone())
+            .collect()
+    }
+
+    /// 选择列表中的提示，如 ` ⚠ in use by cargo (pid 1234)`；没有进程使用时为空
+    pub fn marker(&self, path: &Path) -> String {
+        let users = self.users(path);
+        if users.is_empty() {
+            return String::new();
+        }
+        format!(" ⚠ in use by {}", Self::describe(&users))
+            .yellow()
+            .to_string()
+    }
+
+    /// 以 `cargo (pid 1234), rustc (pid 1240)` 的形式列出进程
+    pub fn describe(processes: &[Process]) -> String {
+        processes
+            .iter()
+            .map(Process::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lsof_groups_files_by_process() {
+        let output = "p101\ncsolana-test-val\nn/home/u/.local/share/solana/install/releases/1.18.0/bin/solana-test-validator\nnpipe\np202\nccargo\nn/home/u/.rustup/toolchains/stable/bin/cargo\n";
+        let processes = Processes::parse_lsof(output);
+
+        let users = processes.users(Path::new("/home/u/.rustup/toolchains/stable"));
+        assert_eq!(
+            users,
+            [Process {
+                pid: 202,
+                name: "cargo".to_string()
+            }]
+        );
+        assert_eq!(
+            Processes::describe(&processes.users(Path::new(
+                "/home/u/.local/share/solana/install/releases/1.18.0"
+            ))),
+            "solana-test-val (pid 101)"
+        );
+        assert!(
+            processes
+                .users(Path::new("/home/u/.rustup/toolchains/nightly"))
+                .is_empty()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn from_proc_reads_executables_and_open_files() {
+        use std::os::unix::fs::symlink;
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("cleanup_proc_test_{}", nanos));
+        let process = root.join("4242");
+        fs::create_dir_all(process.join("fd")).expect("Failed to create fake process");
+        fs::write(process.join("comm"), "cargo\n").expect("Failed to write comm");
+        symlink(
+            "/opt/toolchains/stable/bin/cargo (deleted)",
+            process.join("exe"),
+        )
+        .expect("Failed to link exe");
+        symlink("/work/project/target/debug/app", process.join("fd/3")).expect("Failed to link fd");
+        symlink("pipe:[123]", process.join("fd/4")).expect("Failed to link fd");
+        fs::create_dir_all(root.join("self")).expect("Failed to create non-pid entry");
+
+        let processes = Processes::from_proc(&root);
+        let cargo = [Process {
+            pid: 4242,
+            name: "cargo".to_string(),
+        }];
+        assert_eq!(processes.users(Path::new("/opt/toolchains/stable")), cargo);
+        assert_eq!(processes.users(Path::new("/work/project/target")), cargo);
+        assert!(
+            processes
+                .users(Path::new("/opt/toolchains/beta"))
+                .is_empty()
+        );
+        fs::remove_dir_all(&root).expect("Failed to remove temp directory");
+    }
+}
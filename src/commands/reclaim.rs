@@ -0,0 +1,672 @@
+use colored::Colorize;
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::commands::{
+    Backend, CargoBin, CleanupError, Config, Custom, DirSize, Env, LastUse, Processes, Removal,
+    Removals, Result, Rust, Scan, Sccache, Simulator, Solana, SolanaLedger, Status, TargetDir,
+    Utils, cargo_bin::CrateInfo, config::retained, custom::CustomInfo, rust::RustInfo,
+    sccache::SccacheEntry, simulator::SimulatorInfo, solana::SolanaInfo, solana_ledger::LedgerInfo,
+    target_dir::BuildUnit,
+};
+
+/// 候选条目的删除方式
+pub(crate) enum Action {
+    Rust(RustInfo),
+    Solana(SolanaInfo),
+    Simulator(SimulatorInfo),
+    Custom(CustomInfo),
+    CargoBin(CrateInfo),
+    /// 整个 sccache 缓存
+    Sccache(Vec<SccacheEntry>),
+    /// target 目录中的一个 profile，附带审计记录使用的名称
+    TargetDir(String, BuildUnit),
+    SolanaLedger(LedgerInfo),
+}
+
+impl Action {
+    /// 条目在磁盘上的位置；由外部工具管理的条目（模拟器设备、cargo install 的 crate）
+    /// 与 sccache 缓存没有单一路径
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match self {
+            Action::Rust(toolchain) => Some(&toolchain.path),
+            Action::Solana(version) => Some(&version.path),
+            Action::Simulator(simulator) => simulator.path.as_deref(),
+            Action::Custom(item) => Some(&item.path),
+            Action::CargoBin(_) | Action::Sccache(_) => None,
+            Action::TargetDir(_, unit) => Some(&unit.path),
+            Action::SolanaLedger(ledger) => Some(&ledger.path),
+        }
+    }
+
+    pub(crate) fn backend(&self) -> Backend {
+        match self {
+            Action::Rust(_) => Backend::Tool("rustup"),
+            Action::Simulator(simulator) => Simulator::backend(simulator),
+            Action::CargoBin(_) => Backend::Tool("cargo"),
+            Action::Solana(_)
+            | Action::Custom(_)
+            | Action::Sccache(_)
+            | Action::TargetDir(..)
+            | Action::SolanaLedger(_) => Backend::Filesystem,
+        }
+    }
+
+    /// 用对应模块的删除函数删除条目；`category` 为自定义目标的名称
+    pub(crate) fn remove(&self, env: &Env, category: &str) -> Result<()> {
+        match self {
+            Action::Rust(toolchain) => Rust::remove_toolchain(env, toolchain),
+            Action::Solana(version) => Solana::remove_version(env, version),
+            Action::Simulator(simulator) => Simulator::remove_simulator(env, simulator),
+            Action::Custom(item) => Custom::remove_item(env, category, item),
+            Action::CargoBin(krate) => CargoBin::remove_crate(env, krate),
+            Action::Sccache(entries) => {
+                let size = entries.iter().map(|e| e.size.reclaimable).sum();
+                Sccache::remove_entries(env, entries, size)
+            }
+            Action::TargetDir(name, unit) => TargetDir::remove_unit(env, name, unit),
+            Action::SolanaLedger(ledger) => SolanaLedger::remove_ledger(env, ledger),
+        }
+    }
+}
+
+/// 所有清理目标中的条目
+pub(crate) struct Candidate {
+    pub(crate) category: String,
+    pub(crate) name: String,
+    pub(crate) size: DirSize,
+    pub(crate) last_use: LastUse,
+    /// 越小越安全：0 可再生的缓存，1 已不可用的模拟器，2 未使用的条目，3 可用的模拟器设备，
+    /// 4 仍被设备引用的运行时
+    pub(crate) safety: u8,
+    /// 不可删除的原因：使用中、受保护或按保留策略保留；为空时可以删除
+    pub(crate) lock: Option<&'static str>,
+    pub(crate) action: Action,
+}
+
+impl Candidate {
+    /// 排序键：先按安全等级，再按最近使用时间从旧到新（时间未知的排在最后），最后大的优先
+    fn rank(&self) -> (u8, u64, Reverse<u64>) {
+        let latest = self.last_use.modified.max(self.last_use.accessed);
+        let latest = if latest == 0 { u64::MAX } else { latest };
+        (self.safety, latest, Reverse(self.size.reclaimable))
+    }
+}
+
+/// 各目标对自己条目的描述，由 [`Reclaim::extend`] 统一判断能否删除
+struct Entry<'a> {
+    name: String,
+    /// 除名称外也与 `protect` 匹配的标识，如模拟器的标识符
+    alias: Option<&'a str>,
+    size: DirSize,
+    last_use: LastUse,
+    safety: u8,
+    /// 目标自身判定的不可删除原因，如使用中、当前构建
+    busy: Option<&'static str>,
+    /// 参与 `keep_latest` 的路径；为空时不参与
+    retainable: Option<&'a Path>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(name: impl Into<String>, size: DirSize, last_use: LastUse) -> Self {
+        Entry {
+            name: name.into(),
+            alias: None,
+            size,
+            last_use,
+            safety: 2,
+            busy: None,
+            retainable: None,
+        }
+    }
+
+    fn alias(mut self, alias: &'a str) -> Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    fn safety(mut self, safety: u8) -> Self {
+        self.safety = safety;
+        self
+    }
+
+    fn busy(mut self, busy: Option<&'static str>) -> Self {
+        self.busy = busy;
+        self
+    }
+
+    fn retainable(mut self, path: Option<&'a Path>) -> Self {
+        self.retainable = path;
+        self
+    }
+}
+
+/// 从所有清理目标中挑选条目，释放指定大小的空间
+pub struct Reclaim;
+
+impl Reclaim {
+    /// 条目不可删除的原因：目标自身的原因优先，其次是受保护与按保留策略保留
+    fn lock_reason(config: &Config, entry: &Entry, kept: &HashSet<&Path>) -> Option<&'static str> {
+        let protected = std::iter::once(entry.name.as_str())
+            .chain(entry.alias)
+            .any(|name| config.is_protected(name));
+        if entry.busy.is_some() {
+            entry.busy
+        } else if protected {
+            Some("protected")
+        } else if entry.retainable.is_some_and(|path| kept.contains(path)) {
+            Some("kept by keep_latest")
+        } else {
+            None
+        }
+    }
+
+    /// 把一个目标的条目加入候选列表；`describe` 描述条目，`action` 给出删除方式
+    fn extend<T>(
+        candidates: &mut Vec<Candidate>,
+        config: &Config,
+        category: &str,
+        keep_latest: usize,
+        items: Vec<T>,
+        describe: impl for<'a> Fn(&'a T) -> Entry<'a>,
+        action: impl Fn(T) -> Action,
+    ) {
+        let described: Vec<_> = {
+            let entries: Vec<Entry> = items.iter().map(&describe).collect();
+            let kept: HashSet<&Path> = retained(
+                keep_latest,
+                entries.iter().filter_map(|entry| entry.retainable),
+            )
+            .into_iter()
+            .collect();
+            entries
+                .iter()
+                .map(|entry| {
+                    let lock = Self::lock_reason(config, entry, &kept);
+                    (
+                        entry.name.clone(),
+                        entry.size,
+                        entry.last_use,
+                        entry.safety,
+                        lock,
+                    )
+                })
+                .collect()
+        };
+        candidates.extend(items.into_iter().zip(described).map(
+            |(item, (name, size, last_use, safety, lock))| Candidate {
+                category: category.to_string(),
+                name,
+                size,
+                last_use,
+                safety,
+                lock,
+                action: action(item),
+            },
+        ));
+    }
+
+    /// 模拟器条目的安全等级；仍被设备引用的运行时即使已不可用也不计入计划
+    fn simulator_safety(simulator: &SimulatorInfo, referenced: &[String]) -> u8 {
+        if simulator.is_cache() {
+            0
+        } else if referenced.contains(&simulator.identifier) {
+            4
+        } else if !simulator.is_available {
+            1
+        } else if simulator.simulator_type == "device" {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// 使用中的条目不参与保留策略
+    fn active(is_active: bool, path: &Path) -> (Option<&'static str>, Option<&Path>) {
+        if is_active {
+            (Some("active"), None)
+        } else {
+            (None, Some(path))
+        }
+    }
+
+    /// 扫描所有已启用的目标，收集全部条目并标出不可删除的原因，供向导、`reclaim` 与全屏浏览共用。
+    /// target 目录与测试账本只在配置了 `roots` 时查找
+    pub(crate) fn collect(env: &Env) -> Vec<Candidate> {
+        let config = env.settings();
+        let mut candidates = Vec::new();
+
+        if config.rust.enabled {
+            let rusts = Status::category("Rust", Rust::get_rusts(env));
+            Self::extend(
+                &mut candidates,
+                config,
+                "Rust",
+                config.rust.keep_latest,
+                rusts,
+                |r| {
+                    let (busy, retainable) = Self::active(r.is_active, &r.path);
+                    Entry::new(&r.name, r.size, r.last_use)
+                        .busy(busy)
+                        .retainable(retainable)
+                },
+                Action::Rust,
+            );
+        }
+
+        if config.solana.enabled {
+            let solanas = Status::category("Solana", Solana::get_solanas(env));
+            Self::extend(
+                &mut candidates,
+                config,
+                "Solana",
+                config.solana.keep_latest,
+                solanas,
+                |s| {
+                    let (busy, retainable) = Self::active(s.is_active, &s.path);
+                    Entry::new(&s.name, s.size, s.last_use)
+                        .busy(busy)
+                        .retainable(retainable)
+                },
+                Action::Solana,
+            );
+        }
+
+        if config.simulator.enabled {
+            let tree = Status::category(
+                "Simulator",
+                Simulator::get_simulator_tree(env, |_| {}).map(|tree| vec![tree]),
+            )
+            .pop();
+            if let Some(tree) = tree {
+                let referenced = tree.referenced_runtimes();
+                Self::extend(
+                    &mut candidates,
+                    config,
+                    "Simulator",
+                    0,
+                    tree.into_items(),
+                    |s| {
+                        Entry::new(&s.name, s.size, s.last_use)
+                            .alias(&s.identifier)
+                            .safety(Self::simulator_safety(s, &referenced))
+                    },
+                    Action::Simulator,
+                );
+            }
+        }
+
+        if config.cargo_bin.enabled {
+            let crates = Status::category("Cargo", CargoBin::get_crates(env));
+            Self::extend(
+                &mut candidates,
+                config,
+                "cargo-bin",
+                config.cargo_bin.keep_latest,
+                crates,
+                |c| {
+                    Entry::new(&c.name, c.size, c.last_use)
+                        .retainable(c.binaries.first().map(PathBuf::as_path))
+                },
+                Action::CargoBin,
+            );
+        }
+
+        if config.sccache.enabled {
+            let entries = Status::category("sccache", Sccache::get_entries(env));
+            let caches = if entries.is_empty() {
+                Vec::new()
+            } else {
+                vec![entries]
+            };
+            Self::extend(
+                &mut candidates,
+                config,
+                "sccache",
+                0,
+                caches,
+                |entries| {
+                    let size = entries.iter().map(|e| e.size).sum();
+                    let last_use = entries
+                        .iter()
+                        .fold(LastUse::default(), |last, e| last.merge(e.last_use));
+                    let name = format!("cache ({} entries)", entries.len());
+                    Entry::new(name, size, last_use).safety(0)
+                },
+                Action::Sccache,
+            );
+        }
+
+        if config.target_dir.enabled && !config.target_dir.roots.is_empty() {
+            let dirs = Status::category("Target dirs", TargetDir::get_target_dirs(env, &[]));
+            let installed = if dirs.is_empty() {
+                None
+            } else {
+                TargetDir::installed(env)
+            };
+            let mut units = Vec::new();
+            for dir in dirs {
+                let project = dir.project();
+                let current = dir.current().map(|unit| unit.path.clone());
+                let stale: Vec<PathBuf> =
+                    TargetDir::stale_units(&dir, installed.as_deref(), &config.filter)
+                        .into_iter()
+                        .map(|(unit, _)| unit.path.clone())
+                        .collect();
+                units.extend(dir.units.into_iter().map(|unit| {
+                    let name = format!("{}: {}", project, unit.label());
+                    let is_current = Some(&unit.path) == current.as_ref();
+                    let is_stale = stale.contains(&unit.path);
+                    (name, unit, is_current, is_stale)
+                }));
+            }
+            Self::extend(
+                &mut candidates,
+                config,
+                "target-dir",
+                0,
+                units,
+                |(name, unit, is_current, is_stale)| {
+                    Entry::new(name, unit.size, unit.last_use)
+                        .safety(if *is_stale { 0 } else { 2 })
+                        .busy(is_current.then_some("current build"))
+                },
+                |(name, unit, _, _)| Action::TargetDir(name, unit),
+            );
+        }
+
+        if config.solana_ledger.enabled && !config.solana_ledger.roots.is_empty() {
+            let processes = Processes::snapshot(env);
+            let ledgers = Status::category(
+                "Solana ledgers",
+                SolanaLedger::get_ledgers(env, &[], &processes),
+            );
+            Self::extend(
+                &mut candidates,
+                config,
+                "solana-ledger",
+                0,
+                ledgers,
+                |ledger| {
+                    Entry::new(&ledger.name, ledger.size, ledger.last_use)
+                        .safety(0)
+                        .busy((!ledger.running.is_empty()).then_some("validator running"))
+                },
+                Action::SolanaLedger,
+            );
+        }
+
+        for target in config.custom.iter().filter(|target| target.enabled) {
+            let items = Status::category(&target.name, Custom::get_items(env, target));
+            Self::extend(
+                &mut candidates,
+                config,
+                &target.name,
+                target.keep_latest,
+                items,
+                |item| {
+                    let (busy, retainable) = Self::active(item.is_active, &item.path);
+                    Entry::new(&item.name, item.size, item.last_use)
+                        .busy(busy)
+                        .retainable(retainable)
+                },
+                Action::Custom,
+            );
+        }
+
+        candidates
+    }
+
+    /// 所有已启用目标中未在使用、未受保护的条目
+    pub(crate) fn candidates(env: &Env) -> Vec<Candidate> {
+        Self::collect(env)
+            .into_iter()
+            .filter(|candidate| candidate.lock.is_none())
+            .collect()
+    }
+
+    /// 按安全程度排序候选条目，取出刚好达到目标大小的前缀；仍被设备引用的运行时从不计入，
+    /// 可用的模拟器设备只在 `include_devices` 时计入
+    fn plan(mut candidates: Vec<Candidate>, target: u64, include_devices: bool) -> Vec<Candidate> {
+        let limit = if include_devices { 3 } else { 2 };
+        candidates.retain(|candidate| candidate.safety <= limit);
+        candidates.sort_by_key(Candidate::rank);
+        let mut freed = 0;
+        candidates
+            .into_iter()
+            .take_while(|candidate| {
+                let needed = freed < target;
+                freed += candidate.size.reclaimable;
+                needed
+            })
+            .collect()
+    }
+
+    /// 按执行方式分批删除计划中的条目；释放量只比较被删除条目删除前后的大小，不重新扫描其它条目
+    pub(crate) fn execute(env: &Env, plan: &[&Candidate]) -> Removals {
+        let removals = plan
+            .iter()
+            .map(|candidate| {
+                Removal::new(
+                    &candidate.category,
+                    &candidate.name,
+                    candidate.size.reclaimable,
+                    candidate.action.path(),
+                    candidate.action.backend(),
+                    || candidate.action.remove(env, &candidate.category),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals)
+    }
+
+    /// 计算并执行释放 `target` 字节的计划；`yes` 为 true 时跳过确认，
+    /// `include_devices` 为 true 时可用的模拟器设备也可被删除
+    pub fn reclaim(env: &Env, target: u64, yes: bool, include_devices: bool) -> Result<()> {
+        println!(
+            "{}",
+            format!("♻️  Reclaim plan to free {}:", Utils::format_size(target))
+                .bold()
+                .cyan()
+        );
+        println!();
+
+        env.home()?;
+        Scan::begin("Scanning");
+        let candidates = Self::candidates(env);
+        Scan::finish();
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+
+        let plan = Self::plan(candidates, target, include_devices);
+        if plan.is_empty() {
+            println!("Nothing to reclaim.");
+            return Ok(());
+        }
+
+        let mut total = 0;
+        for (index, candidate) in plan.iter().enumerate() {
+            total += candidate.size.reclaimable;
+            println!(
+                "{:>3}. {:<12} {:<45} {:>10} {:>8}  {}",
+                index + 1,
+                candidate.category,
+                candidate.name,
+                Utils::format_size(candidate.size.reclaimable).yellow(),
+                Utils::format_age(&candidate.last_use).dimmed(),
+                Utils::format_size(total).dimmed()
+            );
+        }
+        println!();
+        if total < target {
+            println!(
+                "{} Only {} can be reclaimed, short of the {} target.",
+                "⚠".yellow(),
+                Utils::format_size(total).bold(),
+                Utils::format_size(target)
+            );
+        }
+
+        let prompt = format!(
+            "Remove {} items to free {}?",
+            plan.len(),
+            Utils::format_size(total)
+        );
+        if !yes && !env.confirm(&prompt).map_err(CleanupError::Prompt)? {
+            println!("Nothing removed.");
+            return Ok(());
+        }
+
+        let plan: Vec<&Candidate> = plan.iter().collect();
+        Self::execute(env, &plan).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ScriptedRunner;
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn candidate(name: &str, safety: u8, modified: u64, size: u64) -> Candidate {
+        let info = SolanaInfo {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            size: DirSize::from_logical(size),
+            is_active: false,
+            version: None,
+            last_use: LastUse {
+                modified,
+                accessed: 0,
+            },
+        };
+        Candidate {
+            category: "Solana".to_string(),
+            name: name.to_string(),
+            size: info.size,
+            last_use: info.last_use,
+            safety,
+            lock: None,
+            action: Action::Solana(info),
+        }
+    }
+
+    #[test]
+    fn plan_prefers_safe_and_old_items_until_target() {
+        let candidates = vec![
+            candidate("recent", 2, 2_000, 40),
+            candidate("cache", 0, 3_000, 10),
+            candidate("old", 2, 1_000, 40),
+            candidate("unknown-age", 2, 0, 40),
+            candidate("device", 3, 400, 40),
+            candidate("referenced", 4, 500, 40),
+        ];
+
+        let names =
+            |plan: Vec<Candidate>| -> Vec<String> { plan.into_iter().map(|c| c.name).collect() };
+        let plan = Reclaim::plan(candidates, 60, false);
+        assert_eq!(names(plan), ["cache", "old", "recent"]);
+
+        // 不够时也不会动用仍被引用的运行时，可用设备需要显式开启
+        let everything = || {
+            vec![
+                candidate("old", 2, 1_000, 40),
+                candidate("device", 3, 400, 40),
+                candidate("referenced", 4, 500, 40),
+            ]
+        };
+        assert_eq!(names(Reclaim::plan(everything(), 1_000, false)), ["old"]);
+        assert_eq!(
+            names(Reclaim::plan(everything(), 1_000, true)),
+            ["old", "device"]
+        );
+
+        assert!(Reclaim::plan(Vec::new(), 60, false).is_empty());
+        assert!(Reclaim::plan(vec![candidate("any", 2, 1, 10)], 0, false).is_empty());
+    }
+
+    #[test]
+    fn referenced_runtimes_stay_out_of_plans_even_when_unavailable() {
+        let runtime = |identifier: &str, is_available: bool| SimulatorInfo {
+            name: identifier.to_string(),
+            identifier: identifier.to_string(),
+            version: None,
+            size: DirSize::from_logical(40),
+            last_use: LastUse::default(),
+            is_available,
+            simulator_type: "runtime".to_string(),
+            path: None,
+        };
+        let referenced = vec!["iOS-16".to_string()];
+        assert_eq!(
+            Reclaim::simulator_safety(&runtime("iOS-16", false), &referenced),
+            4
+        );
+        assert_eq!(
+            Reclaim::simulator_safety(&runtime("iOS-15", false), &referenced),
+            1
+        );
+    }
+
+    #[test]
+    fn collect_covers_build_cache_targets_and_locks_current_items() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("cleanup_reclaim_test_{}", nanos));
+        let write = |path: PathBuf| {
+            fs::create_dir_all(path.parent().expect("file has a parent"))
+                .expect("Failed to create dir");
+            fs::write(path, [0u8; 4096]).expect("Failed to write file");
+        };
+        write(root.join("sccache/a/0/entry"));
+        write(root.join("app/target/debug/deps/libapp.rlib"));
+        write(root.join("app/target/release/deps/libapp.rlib"));
+        fs::write(root.join("app/target/.rustc_info.json"), "{}").expect("Failed to write info");
+        write(root.join("counter/test-ledger/genesis.bin"));
+
+        let mut config = Config::default();
+        config.rust.enabled = false;
+        config.solana.enabled = false;
+        config.simulator.enabled = false;
+        let unconfigured = Env::with_home(&root)
+            .var_override("SCCACHE_DIR", &root.join("sccache").to_string_lossy())
+            .runner(ScriptedRunner::new())
+            .config(config.clone());
+        let categories = |candidates: &[Candidate]| -> Vec<String> {
+            candidates.iter().map(|c| c.category.clone()).collect()
+        };
+        // 未配置 roots 时不扫描当前目录
+        assert_eq!(categories(&Reclaim::collect(&unconfigured)), ["sccache"]);
+
+        config.target_dir.roots = vec![root.clone()];
+        config.solana_ledger.roots = vec![root.clone()];
+        let env = Env::with_home(&root)
+            .var_override("SCCACHE_DIR", &root.join("sccache").to_string_lossy())
+            .runner(ScriptedRunner::new())
+            .config(config);
+        let candidates = Reclaim::collect(&env);
+        assert_eq!(
+            categories(&candidates),
+            ["sccache", "target-dir", "target-dir", "solana-ledger"]
+        );
+        let locked: Vec<&str> = candidates
+            .iter()
+            .filter(|c| c.lock.is_some())
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(locked.len(), 1);
+        assert!(locked[0].starts_with("app: "));
+        assert_eq!(Reclaim::candidates(&env).len(), 3);
+        fs::remove_dir_all(&root).expect("Failed to remove temp directory");
+    }
+}
@@ -0,0 +1,498 @@
+use colored::Colorize;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{self, Write},
+    os::fd::FromRawFd,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::commands::{
+    CleanupError, Disk, Env, OutputFormat, Process, Processes, Result, Scan, Utils,
+};
+
+/// 输出 JSON 时保留下来的原始标准输出
+static SUMMARY_OUT: OnceLock<File> = OnceLock::new();
+
+/// 删除由谁完成：直接删除文件的可以并行，经由外部工具的必须逐个执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Backend {
+    Filesystem,
+    /// 外部工具的命令名，如 `rustup`、`xcrun`
+    Tool(&'static str),
+}
+
+/// 一次待执行的删除
+pub struct Removal<'a> {
+    target: String,
+    item: String,
+    size: u64,
+    path: Option<PathBuf>,
+    backend: Backend,
+    remove: Box<dyn FnOnce() -> Result<()> + Send + 'a>,
+}
+
+impl<'a> Removal<'a> {
+    pub fn new(
+        target: &str,
+        item: &str,
+        size: u64,
+        path: Option<&Path>,
+        backend: Backend,
+        remove: impl FnOnce() -> Result<()> + Send + 'a,
+    ) -> Self {
+        Removal {
+            target: target.to_string(),
+            item: item.to_string(),
+            size,
+            path: path.map(Path::to_path_buf),
+            backend,
+            remove: Box::new(remove),
+        }
+    }
+}
+
+/// 一次删除的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RemovalResult {
+    pub target: String,
+    pub item: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// 删除前测得的可回收大小
+    pub size: u64,
+    /// 实际释放的字节数：删除前的大小减去删除后剩余的大小
+    pub freed: u64,
+    /// 所在卷可用空间的变化；无法读取卷信息时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_freed: Option<i64>,
+    pub removed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 一次运行中所有删除的汇总
+#[derive(Debug, Default, Serialize)]
+pub struct Removals {
+    pub removed: usize,
+    pub failed: usize,
+    pub freed: u64,
+    pub disk_freed: i64,
+    pub items: Vec<RemovalResult>,
+}
+
+impl Removals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 删除后路径下仍然占用的字节数；没有路径（由外部工具管理）时按结果估算
+    fn remaining(path: Option<&Path>, size: u64, removed: bool) -> u64 {
+        match path {
+            Some(path) if path.exists() => Utils::scan_dir(path).size.reclaimable,
+            Some(_) => 0,
+            None if removed => 0,
+            None => size,
+        }
+    }
+
+    /// 执行一次删除并测量释放的空间；`measure_disk` 为 true 时记录所在卷可用空间的变化，
+    /// `path` 为空时用主目录所在的卷
+    fn perform(env: &Env, removal: Removal, measure_disk: bool) -> RemovalResult {
+        let Removal {
+            target,
+            item,
+            size,
+            path,
+            remove,
+            ..
+        } = removal;
+        let volume_path = path.clone().or_else(|| env.home().ok());
+        let free = || {
+            volume_path
+                .as_deref()
+                .filter(|_| measure_disk)
+                .and_then(|path| Disk::volume(path).ok())
+                .map(|volume| volume.free)
+        };
+
+        eprintln!("Removing {}...", item);
+        let before = free();
+        let result = remove();
+        let disk_freed = before
+            .zip(free())
+            .map(|(before, after)| after as i64 - before as i64);
+        let removed = result.is_ok();
+        let freed = size.saturating_sub(Self::remaining(path.as_deref(), size, removed));
+
+        match &result {
+            Ok(_) => eprintln!(
+                "✓ Removed {} (freed {})",
+                item.green(),
+                Utils::format_size(freed).bold()
+            ),
+            Err(err) => eprintln!("✗ Failed to remove {}: {}", item.red(), err),
+        }
+        RemovalResult {
+            target,
+            item,
+            path,
+            size,
+            freed,
+            disk_freed,
+            removed,
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+
+    /// 正被进程使用的条目不执行删除，直接记为失败
+    fn refuse(removal: Removal, processes: Vec<Process>) -> RemovalResult {
+        let path = removal.path.unwrap_or_default();
+        let err = CleanupError::InUse {
+            path: path.clone(),
+            processes,
+        };
+        eprintln!("✗ Skipped {}: {}", removal.item.red(), err);
+        RemovalResult {
+            target: removal.target,
+            item: removal.item,
+            path: Some(path),
+            size: removal.size,
+            freed: 0,
+            disk_freed: None,
+            removed: false,
+            error: Some(err.to_string()),
+        }
+    }
+
+    /// 各卷当前的可用空间，按设备去重
+    fn free_space<'p>(paths: impl IntoIterator<Item = &'p Path>) -> Vec<(u64, PathBuf, u64)> {
+        Disk::volumes(paths)
+            .into_iter()
+            .flatten()
+            .map(|volume| (volume.device, volume.mount, volume.free))
+            .collect()
+    }
+
+    /// 按执行方式分批删除：文件系统上的删除并行进行，同一外部工具的删除逐个进行。
+    /// 单个条目失败不影响其余条目；中断后不再开始新的删除；
+    /// 未加 `--force` 时跳过正被进程使用的条目。
+    /// 并行删除的条目无法单独测量卷可用空间的变化，只计入整批的总量
+    pub fn execute(env: &Env, removals: Vec<Removal>) -> Self {
+        let processes = if env.settings().force || removals.iter().all(|r| r.path.is_none()) {
+            Processes::default()
+        } else {
+            Processes::snapshot(env)
+        };
+        let run = |removal: Removal, measure_disk: bool| {
+            let users = removal
+                .path
+                .as_deref()
+                .map(|path| processes.users(path))
+                .unwrap_or_default();
+            if users.is_empty() {
+                Self::perform(env, removal, measure_disk)
+            } else {
+                Self::refuse(removal, users)
+            }
+        };
+        let (parallel, mut serial): (Vec<_>, Vec<_>) = removals
+            .into_iter()
+            .enumerate()
+            .partition(|(_, removal)| removal.backend == Backend::Filesystem);
+        let mut summary = Removals::new();
+        let mut results: Vec<(usize, RemovalResult)> = Vec::new();
+
+        if !parallel.is_empty() {
+            let before = Self::free_space(parallel.iter().filter_map(|(_, r)| r.path.as_deref()));
+            results.par_extend(
+                parallel
+                    .into_par_iter()
+                    .filter(|_| !Scan::is_cancelled())
+                    .map(|(index, removal)| (index, run(removal, false))),
+            );
+            for (device, mount, free) in before {
+                if let Ok(after) = Disk::volume(&mount)
+                    && after.device == device
+                {
+                    summary.disk_freed += after.free as i64 - free as i64;
+                }
+            }
+        }
+
+        // 同一工具的删除放在一起，按出现顺序逐个执行
+        serial.sort_by_key(|(_, removal)| removal.backend);
+        for (index, removal) in serial {
+            if Scan::is_cancelled() {
+                break;
+            }
+            results.push((index, run(removal, true)));
+        }
+
+        results.sort_by_key(|(index, _)| *index);
+        for (_, result) in results {
+            summary.push(result);
+        }
+        summary
+    }
+
+    /// 合并另一批删除的汇总，如全屏浏览中多次执行的删除
+    pub fn merge(&mut self, other: Removals) {
+        self.removed += other.removed;
+        self.failed += other.failed;
+        self.freed += other.freed;
+        self.disk_freed += other.disk_freed;
+        self.items.extend(other.items);
+    }
+
+    fn push(&mut self, result: RemovalResult) {
+        if result.removed {
+            self.removed += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.freed += result.freed;
+        self.disk_freed += result.disk_freed.unwrap_or(0);
+        self.items.push(result);
+    }
+
+    /// 以 `+1.20 GB` / `-3.00 MB` 的形式展示可用空间的变化
+    fn format_delta(delta: i64) -> String {
+        let sign = if delta < 0 { "-" } else { "+" };
+        format!("{}{}", sign, Utils::format_size(delta.unsigned_abs()))
+    }
+
+    fn print_table(&self) {
+        println!();
+        println!("{}", "📋 Removal summary:".bold().cyan());
+        println!(
+            "   {:<12} {:<45} {:>10} {:>10} {:>11}  Status",
+            "Target", "Item", "Size", "Freed", "Disk"
+        );
+        for result in &self.items {
+            let status = if result.removed {
+                "✓ removed".green()
+            } else {
+                "✗ failed".red()
+            };
+            println!(
+                "   {:<12} {:<45} {:>10} {:>10} {:>11}  {}",
+                result.target,
+                result.item,
+                Utils::format_size(result.size),
+                Utils::format_size(result.freed).yellow(),
+                result
+                    .disk_freed
+                    .map(Self::format_delta)
+                    .unwrap_or_else(|| "-".to_string()),
+                status
+            );
+        }
+        println!();
+        println!(
+            "{} {} removed, {} failed · freed {} (disk free {})",
+            "💾".bold(),
+            self.removed,
+            self.failed,
+            Utils::format_size(self.freed).bold().green(),
+            Self::format_delta(self.disk_freed)
+        );
+    }
+
+    /// 把标准输出留给 JSON 汇总：保存原来的标准输出，其余输出改写到标准错误，
+    /// 使 `--json | jq` 只读到汇总
+    pub fn reserve_stdout() {
+        let _ = io::stdout().flush();
+        let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if saved < 0 {
+            return;
+        }
+        if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+            unsafe { libc::close(saved) };
+            return;
+        }
+        let _ = SUMMARY_OUT.set(unsafe { File::from_raw_fd(saved) });
+    }
+
+    /// 按输出格式打印汇总；有失败时返回部分删除错误
+    pub fn finish(self, env: &Env) -> Result<()> {
+        if self.items.is_empty() {
+            return Ok(());
+        }
+        match env.settings().output.format {
+            OutputFormat::Text => self.print_table(),
+            OutputFormat::Json => match serde_json::to_string(&self) {
+                Ok(json) => match SUMMARY_OUT.get() {
+                    Some(mut out) => {
+                        let _ = writeln!(out, "{}", json);
+                    }
+                    None => println!("{}", json),
+                },
+                Err(err) => eprintln!("Failed to serialize removal summary: {}", err),
+            },
+        }
+        if self.failed == 0 {
+            return Ok(());
+        }
+        Err(CleanupError::PartialDeletion {
+            attempted: self.items.len(),
+            failures: self
+                .items
+                .into_iter()
+                .filter(|result| !result.removed)
+                .map(|result| (result.item, result.error.unwrap_or_default()))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn execute_runs_every_backend_and_summarises_failures() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_removal_test_{}", nanos));
+        let item = home.join("item");
+        std::fs::create_dir_all(&item).expect("Failed to create item");
+        std::fs::write(item.join("data.bin"), [0u8; 4096]).expect("Failed to write data");
+        let size = Utils::scan_dir(&item).size.reclaimable;
+        let env = Env::with_home(&home);
+
+        let removals = Removals::execute(
+            &env,
+            vec![
+                Removal::new("rust", "stable", 100, None, Backend::Tool("rustup"), || {
+                    Err(CleanupError::MissingTool("rustup".to_string()))
+                }),
+                Removal::new(
+                    "models",
+                    "item",
+                    size,
+                    Some(&item),
+                    Backend::Filesystem,
+                    || std::fs::remove_dir_all(&item).map_err(|err| CleanupError::io(&item, err)),
+                ),
+            ],
+        );
+
+        assert_eq!((removals.removed, removals.failed), (1, 1));
+        assert_eq!(removals.freed, size);
+        assert_eq!(removals.items[0].freed, 0);
+        assert_eq!(removals.items[1].disk_freed, None);
+        assert!(!item.exists());
+        assert_eq!(
+            removals.items[0].error.as_deref(),
+            Some("rustup not found in PATH")
+        );
+        match removals.finish(&env) {
+            Err(CleanupError::PartialDeletion {
+                attempted,
+                failures,
+            }) => {
+                assert_eq!(attempted, 2);
+                assert_eq!(failures[0].0, "stable");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        std::fs::remove_dir_all(&home).expect("Failed to remove temp directory");
+    }
+
+    #[test]
+    fn execute_runs_tool_removals_one_at_a_time() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let running = AtomicUsize::new(0);
+        let overlapped = AtomicUsize::new(0);
+        let env = Env::with_home(std::env::temp_dir());
+        let removals = (0..4)
+            .map(|index| {
+                let (running, overlapped) = (&running, &overlapped);
+                Removal::new(
+                    "rust",
+                    &format!("toolchain-{}", index),
+                    10,
+                    None,
+                    Backend::Tool("rustup"),
+                    move || {
+                        if running.fetch_add(1, Ordering::SeqCst) > 0 {
+                            overlapped.fetch_add(1, Ordering::SeqCst);
+                        }
+                        std::thread::sleep(std::time::Duration::from_millis(5));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        if index == 1 {
+                            return Err(CleanupError::MissingTool("rustup".to_string()));
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .collect();
+
+        let removals = Removals::execute(&env, removals);
+        assert_eq!(overlapped.load(Ordering::SeqCst), 0);
+        assert_eq!((removals.removed, removals.failed), (3, 1));
+        let items: Vec<&str> = removals.items.iter().map(|r| r.item.as_str()).collect();
+        assert_eq!(
+            items,
+            ["toolchain-0", "toolchain-1", "toolchain-2", "toolchain-3"]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn execute_skips_items_used_by_running_processes_unless_forced() {
+        use crate::commands::Config;
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_in_use_test_{}", nanos));
+        let item = home.join("1.18.0");
+        std::fs::create_dir_all(&item).expect("Failed to create item");
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .current_dir(&item)
+            .spawn()
+            .expect("Failed to spawn sleep");
+        let removal = || {
+            Removal::new(
+                "solana",
+                "1.18.0",
+                10,
+                Some(&item),
+                Backend::Filesystem,
+                || std::fs::remove_dir_all(&item).map_err(|err| CleanupError::io(&item, err)),
+            )
+        };
+
+        let refused = Removals::execute(&Env::with_home(&home), vec![removal()]);
+        assert_eq!(refused.failed, 1);
+        let error = refused.items[0].error.as_deref().unwrap_or_default();
+        assert!(
+            error.contains(&format!("sleep (pid {})", child.id())),
+            "{}",
+            error
+        );
+        assert!(item.exists());
+
+        let forced = Env::with_home(&home).config(Config {
+            force: true,
+            ..Config::default()
+        });
+        let removed = Removals::execute(&forced, vec![removal()]);
+        child.kill().expect("Failed to kill sleep");
+        child.wait().expect("Failed to wait for sleep");
+        assert_eq!(removed.removed, 1);
+        assert!(!item.exists());
+        std::fs::remove_dir_all(&home).expect("Failed to remove temp directory");
+    }
+}
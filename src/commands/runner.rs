@@ -0,0 +1,289 @@
+use colored::Colorize;
+use std::{
+    ffi::OsStr,
+    fmt,
+    io::{self, Read},
+    process::{Child, Command, Stdio},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::commands::Scan;
+
+/// 外部命令执行失败的原因
+#[derive(Debug)]
+pub enum ProbeError {
+    /// 可执行文件不存在
+    NotFound,
+    /// 进程无法启动或读取输出失败
+    Io(io::Error),
+    /// 超时后被终止
+    TimedOut(Duration),
+    /// 扫描被用户取消
+    Cancelled,
+    /// 以非零状态退出
+    Failed { code: Option<i32>, stderr: String },
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeError::NotFound => write!(f, "command not found"),
+            ProbeError::Io(err) => write!(f, "{}", err),
+            ProbeError::TimedOut(after) => write!(f, "timed out after {}s", after.as_secs_f32()),
+            ProbeError::Cancelled => write!(f, "cancelled"),
+            ProbeError::Failed { code, stderr } => {
+                match code {
+                    Some(code) => write!(f, "exited with code {}", code)?,
+                    None => write!(f, "terminated by signal")?,
+                }
+                match stderr.trim() {
+                    "" => Ok(()),
+                    stderr => write!(f, ": {}", stderr),
+                }
+            }
+        }
+    }
+}
+
+/// 成功执行的命令输出
+#[derive(Debug)]
+pub struct CommandOutput {
+    pub stdout: Vec<u8>,
+}
+
+impl CommandOutput {
+    pub fn stdout_string(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).to_string()
+    }
+}
+
+/// 本次运行中失败或超时的外部命令
+struct ProbeFailure {
+    command: String,
+    error: String,
+}
+
+/// 默认探测超时（毫秒），可通过 `--timeout` 调整
+static PROBE_TIMEOUT_MS: AtomicU64 = AtomicU64::new(10_000);
+static FAILURES: Mutex<Vec<ProbeFailure>> = Mutex::new(Vec::new());
+
+/// 带超时与失败汇总的外部命令执行器
+pub struct Runner;
+
+impl Runner {
+    /// 删除类操作（rustup uninstall、simctl delete）的超时
+    pub const REMOVAL_TIMEOUT: Duration = Duration::from_secs(600);
+
+    /// 设置版本探测等短命令的默认超时
+    pub fn set_probe_timeout(timeout: Duration) {
+        PROBE_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// 版本探测等短命令的超时
+    pub fn probe_timeout() -> Duration {
+        Duration::from_millis(PROBE_TIMEOUT_MS.load(Ordering::Relaxed))
+    }
+
+    /// 执行命令并在超时或 Ctrl-C 后终止它；失败会被记录到本次运行的汇总中
+    pub fn run<I, S>(
+        program: impl AsRef<OsStr>,
+        args: I,
+        timeout: Duration,
+    ) -> Result<CommandOutput, ProbeError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self::execute(program, args, timeout, true)
+    }
+
+    /// 执行删除命令（rustup uninstall、simctl delete）；它不会被 Ctrl-C 中断，
+    /// 取消只在两次删除之间生效，避免留下删除了一半的工具链
+    pub fn run_removal<I, S>(
+        program: impl AsRef<OsStr>,
+        args: I,
+    ) -> Result<CommandOutput, ProbeError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self::execute(program, args, Self::REMOVAL_TIMEOUT, false)
+    }
+
+    fn execute<I, S>(
+        program: impl AsRef<OsStr>,
+        args: I,
+        timeout: Duration,
+        interruptible: bool,
+    ) -> Result<CommandOutput, ProbeError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(program.as_ref());
+        command.args(args);
+        let description = Self::describe(&command);
+
+        let result = Self::run_command(command, timeout, interruptible);
+        if let Err(err) = &result
+            && !matches!(err, ProbeError::Cancelled)
+            && let Ok(mut failures) = FAILURES.lock()
+        {
+            failures.push(ProbeFailure {
+                command: description,
+                error: err.to_string(),
+            });
+        }
+        result
+    }
+
+    fn describe(command: &Command) -> String {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn spawn_reader(mut stream: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stream.read_to_end(&mut buffer);
+            buffer
+        })
+    }
+
+    fn run_command(
+        mut command: Command,
+        timeout: Duration,
+        interruptible: bool,
+    ) -> Result<CommandOutput, ProbeError> {
+        // 终端的 Ctrl-C 会发给整个前台进程组，删除命令放到单独的进程组中
+        #[cfg(unix)]
+        if !interruptible {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let mut child: Child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => ProbeError::NotFound,
+                _ => ProbeError::Io(err),
+            })?;
+
+        let stdout = child.stdout.take().map(Self::spawn_reader);
+        let stderr = child.stderr.take().map(Self::spawn_reader);
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) if interruptible && Scan::is_cancelled() => {
+                    break Err(ProbeError::Cancelled);
+                }
+                Ok(None) if Instant::now() >= deadline => break Err(ProbeError::TimedOut(timeout)),
+                Ok(None) => thread::sleep(Duration::from_millis(20)),
+                Err(err) => break Err(ProbeError::Io(err)),
+            }
+        };
+        let status = match status {
+            Ok(status) => status,
+            Err(err) => {
+                // 子进程可能把管道传给了孙进程，不等待读取线程以免再次阻塞
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(err);
+            }
+        };
+
+        let stdout = stdout.and_then(|h| h.join().ok()).unwrap_or_default();
+        let stderr = stderr.and_then(|h| h.join().ok()).unwrap_or_default();
+
+        if !status.success() {
+            return Err(ProbeError::Failed {
+                code: status.code(),
+                stderr: String::from_utf8_lossy(&stderr).to_string(),
+            });
+        }
+        Ok(CommandOutput { stdout })
+    }
+
+    /// 输出本次运行中失败或超时的外部命令汇总
+    pub fn report_failures() {
+        let Ok(mut failures) = FAILURES.lock() else {
+            return;
+        };
+        if failures.is_empty() {
+            return;
+        }
+        eprintln!();
+        eprintln!(
+            "{}",
+            format!(
+                "⚠ {} external command(s) failed or timed out:",
+                failures.len()
+            )
+            .yellow()
+            .bold()
+        );
+        for failure in failures.drain(..) {
+            eprintln!("  {} — {}", failure.command, failure.error.red());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_kills_commands_that_exceed_timeout() {
+        let started = Instant::now();
+        let result = Runner::run("sleep", ["5"], Duration::from_millis(100));
+        assert!(matches!(result, Err(ProbeError::TimedOut(_))));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn run_captures_stderr_of_failed_commands() {
+        let result = Runner::run(
+            "sh",
+            ["-c", "echo boom >&2; exit 3"],
+            Duration::from_secs(5),
+        );
+        match result {
+            Err(ProbeError::Failed { stderr, .. }) => assert_eq!(stderr.trim(), "boom"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            Runner::run(
+                "cleanup-command-that-should-not-exist",
+                [""; 0],
+                Duration::from_secs(1)
+            ),
+            Err(ProbeError::NotFound)
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn removal_commands_run_outside_the_terminal_process_group() {
+        let args = ["-c", "cut -d' ' -f5 /proc/$$/stat"];
+        let pgid = |output: Result<CommandOutput, ProbeError>| {
+            let output = output.expect("Failed to read process group");
+            output.stdout_string().trim().parse::<i32>().expect("pgid")
+        };
+        let own = unsafe { libc::getpgrp() };
+        // 超时与删除命令相同的探测仍留在终端的进程组中
+        assert_eq!(pgid(Runner::run("sh", args, Runner::REMOVAL_TIMEOUT)), own);
+        assert_ne!(pgid(Runner::run_removal("sh", args)), own);
+    }
+}
@@ -1,189 +1,348 @@
 use colored::Colorize;
-use dialoguer::{MultiSelect, theme::ColorfulTheme};
 use rayon::prelude::*;
 use std::{
+    fs,
     path::{Path, PathBuf},
-    process::Command,
 };
 
-use crate::commands::Utils;
+use crate::commands::{
+    Audit, AuditEntry, Backend, Cache, CleanupError, Config, DirSize, Env, LastUse, Processes,
+    Removal, Removals, Result, Runner, Scan, ScanEvent, Utils,
+};
 pub struct Rust;
 
 #[derive(Debug)]
 pub struct RustInfo {
-    name: String,
-    pub size: u64,
-    is_active: bool,
-    version: Option<String>,
+    pub name: String,
+    pub path: PathBuf,
+    pub size: DirSize,
+    pub is_active: bool,
+    pub version: Option<String>,
+    pub last_use: LastUse,
 }
 
 impl Rust {
     /// 获取单个 Rust 工具链的信息
-    fn get_rust_version_info(_toolchain_path: &PathBuf) -> Option<String> {
-        let rust_bin = _toolchain_path.join("bin/rustc");
+    fn get_rust_version_info(env: &Env, toolchain_path: &Path) -> Option<String> {
+        let rust_bin = toolchain_path.join("bin/rustc");
         if !rust_bin.exists() {
             return None;
         }
-        let output = Command::new(rust_bin).arg("--version").output().ok()?;
+        let output = env
+            .run(rust_bin, ["--version"], Runner::probe_timeout())
+            .ok()?;
 
-        String::from_utf8(output.stdout)
-            .ok()
-            .and_then(|s| s.split_whitespace().nth(1).map(|v| v.to_string()))
+        output
+            .stdout_string()
+            .split_whitespace()
+            .nth(1)
+            .map(|v| v.to_string())
     }
     /// 获取当前激活的 Rust 工具链
-    fn get_active_rust_version() -> Option<String> {
-        if !Utils::command_exists("rustup") {
-            eprintln!("rustup not found in PATH.");
-            return None;
-        }
-        let output = std::process::Command::new("rustup")
-            .args(["show", "active-toolchain"])
-            .output()
+    fn get_active_rust_version(env: &Env) -> Option<String> {
+        let output = env
+            .run(
+                "rustup",
+                ["show", "active-toolchain"],
+                Runner::probe_timeout(),
+            )
             .ok()?;
-        let stdout = String::from_utf8(output.stdout).ok()?;
-        stdout.split_whitespace().next().map(|s| s.to_string())
+        output
+            .stdout_string()
+            .split_whitespace()
+            .next()
+            .map(|s| s.to_string())
     }
-    /// 获取所有 Rust 工具链
-    pub fn get_rusts() -> Vec<RustInfo> {
-        if !Utils::command_exists("rustup") {
-            eprintln!("rustup command not available; skipping Rust toolchain inspection.");
-            return Vec::new();
+    /// 所有已安装工具链的 `rustc --version` 输出，如 `rustc 1.75.0 (82e1608df 2023-12-21)`；
+    /// 不受 `--include`/`--unused-for` 等过滤条件影响
+    pub fn installed_versions(env: &Env) -> Result<Vec<String>> {
+        if !env.command_exists("rustup") {
+            return Err(CleanupError::MissingTool("rustup".to_string()));
         }
-        let home = match std::env::var("HOME") {
-            Ok(path) => path,
-            Err(err) => {
-                eprintln!("HOME environment variable is not set: {}", err);
-                return Vec::new();
-            }
+        let root = env.settings().rust.root()?;
+        let entries = match fs::read_dir(&root) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(CleanupError::io(&root, err)),
         };
-        let rustup_path = Path::new(&home).join(".rustup/toolchains");
+        let paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|e| e.path())
+            .collect();
+        Ok(paths
+            .into_par_iter()
+            .filter_map(|path| {
+                let output = env
+                    .run(
+                        path.join("bin/rustc"),
+                        ["--version"],
+                        Runner::probe_timeout(),
+                    )
+                    .ok()?;
+                let version = output.stdout_string().lines().next()?.trim().to_string();
+                (!version.is_empty()).then_some(version)
+            })
+            .collect())
+    }
+
+    /// 获取所有 Rust 工具链
+    pub fn get_rusts(env: &Env) -> Result<Vec<RustInfo>> {
+        Self::scan_rusts(env, |_| {})
+    }
+
+    /// 扫描所有 Rust 工具链，每扫描完一个就调用 `on_item`
+    pub fn scan_rusts(env: &Env, on_item: impl Fn(&RustInfo) + Sync) -> Result<Vec<RustInfo>> {
+        if !env.command_exists("rustup") {
+            return Err(CleanupError::MissingTool("rustup".to_string()));
+        }
+        let rustup_path = env.settings().rust.root()?;
         if !rustup_path.exists() {
-            return Vec::new();
+            return Ok(Vec::new());
         }
 
-        let active_toolchain = Self::get_active_rust_version();
+        let active_toolchain = Self::get_active_rust_version(env);
 
-        let entries = match std::fs::read_dir(&rustup_path) {
-            Ok(entries) => entries,
-            Err(err) => {
-                eprintln!("Failed to read Rust toolchain directory: {}", err);
-                return Vec::new();
-            }
-        };
+        let entries =
+            std::fs::read_dir(&rustup_path).map_err(|err| CleanupError::io(&rustup_path, err))?;
 
-        let paths: Vec<std::path::PathBuf> = entries
+        let filter = &env.settings().filter;
+        let paths: Vec<PathBuf> = entries
             .filter_map(|entry| entry.ok())
             .map(|e| e.path())
             .filter(|p| p.exists())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| filter.matches(name))
+            })
             .collect();
+        Scan::emit(ScanEvent::Discovered(paths.len() as u64));
 
-        paths
+        let rusts = paths
             .into_par_iter()
+            .filter(|_| !Scan::is_cancelled())
             .filter_map(|path| {
-                let name = path.as_os_str().to_str()?.to_string();
-                let size = Utils::calculate_dir_size(&path);
+                let name = path.file_name()?.to_str()?.to_string();
+                let (size, last_use) = Cache::measure_dir(&path);
+                // 工具链二进制的访问时间最能反映最近一次使用
+                let last_use = fs::metadata(path.join("bin/rustc"))
+                    .map(|metadata| last_use.merge(LastUse::of(&metadata)))
+                    .unwrap_or(last_use);
+                if !filter.is_unused(&last_use) {
+                    Scan::emit(ScanEvent::Completed);
+                    return None;
+                }
                 let is_active = active_toolchain.as_ref().is_some_and(|v| name.contains(v));
-                let version = Self::get_rust_version_info(&path);
-                Some(RustInfo {
+                let version = Self::get_rust_version_info(env, &path);
+                let info = RustInfo {
                     name,
+                    path,
                     size,
                     is_active,
                     version,
-                })
+                    last_use,
+                };
+                Scan::emit(ScanEvent::Completed);
+                on_item(&info);
+                Some(info)
             })
-            .collect()
+            .collect();
+        Ok(rusts)
+    }
+
+    /// 格式化单个工具链的列表行
+    fn format_row(config: &Config, version: &RustInfo) -> String {
+        let status = if version.is_active {
+            "✓ active".green()
+        } else {
+            "  inactive".red()
+        };
+        let version_info = version
+            .version
+            .as_ref()
+            .map(|v| format!(" ({})", v))
+            .unwrap_or_default();
+
+        let name = format!(
+            "{}{}{}",
+            version.name,
+            version_info,
+            config.lock_marker(&version.name)
+        );
+        let size_color = Utils::format_dir_size(&version.size).yellow();
+        let age = Utils::format_age(&version.last_use).dimmed();
+        format!("{} {} {} {}", name, size_color, age, status)
     }
 
     /// 列出所有 Rust 工具链
-    pub fn show_rust_versions() {
+    pub fn show_rust_versions(env: &Env) -> Result<()> {
         println!("{}", "🦀 Rust versions:".bold().cyan());
         println!();
+        if !env.settings().rust.enabled {
+            println!("Rust is disabled in config.\n");
+            return Ok(());
+        }
 
-        let versions = Self::get_rusts();
+        Scan::begin("Rust");
+        let versions = Self::scan_rusts(env, |version| {
+            Scan::println(Self::format_row(env.settings(), version))
+        });
+        Scan::finish();
+        let versions = versions?;
         if versions.is_empty() {
             println!("No Rust versions found.\n");
-            return;
-        }
-
-        let mut total_size = 0;
-        for version in &versions {
-            total_size += version.size;
-            let status = if version.is_active {
-                "✓ active".green()
-            } else {
-                "  inactive".red()
-            };
-            let version_info = version
-                .version
-                .as_ref()
-                .map(|v| format!(" ({})", v))
-                .unwrap_or_default();
-
-            let name = format!("{}{}", version.name, version_info);
-            let size_color = Utils::format_size(version.size).yellow();
-            println!("{} {} {}", name, size_color, status);
+            return Ok(());
         }
 
+        let total_size: DirSize = versions.iter().map(|v| v.size).sum();
         println!();
         println!(
             "Total: {} version, {}",
             versions.len(),
-            Utils::format_size(total_size).bold()
+            Utils::format_dir_size(&total_size).bold()
         );
+        Ok(())
+    }
+
+    /// 通过 rustup 卸载一个工具链
+    pub fn remove_toolchain(env: &Env, toolchain: &RustInfo) -> Result<()> {
+        let args = ["uninstall", toolchain.name.as_str()];
+        let command = format!("rustup {}", args.join(" "));
+        let entry = AuditEntry::new("rust", &toolchain.name, toolchain.size.reclaimable)
+            .path(&toolchain.path)
+            .command(command.clone());
+        Audit::track(env, entry, || {
+            env.run_removal("rustup", args)
+                .map(|_| ())
+                .map_err(|err| CleanupError::command(&command, err))
+        })
     }
 
-    pub fn clean_rust_versions() {
+    pub fn clean_rust_versions(env: &Env) -> Result<()> {
         println!("{}", "🦀 Rust clean:".bold().cyan());
         println!();
-
-        if !Utils::command_exists("rustup") {
-            eprintln!("rustup command not available; cannot clean toolchains.");
-            return;
+        let config = env.settings();
+        if !config.rust.enabled {
+            println!("Rust is disabled in config.\n");
+            return Ok(());
         }
 
-        let list = Self::get_rusts();
+        Scan::begin("Rust");
+        let list = Self::get_rusts(env);
+        Scan::finish();
+        let list = list?;
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
         if list.is_empty() {
             println!("No Rust versions found.\n");
-            return;
+            return Ok(());
         }
 
         println!("{}", "🧹 Cleaning Rust Versions:".bold().cyan());
 
-        let selections = match MultiSelect::with_theme(&ColorfulTheme::default())
-            .items(
-                list.iter()
-                    .map(|v| format!("{} ({})", v.name, Utils::format_size(v.size)))
-                    .collect::<Vec<String>>(),
-            )
-            .interact()
-        {
-            Ok(selection) => selection,
-            Err(err) => {
-                eprintln!("Failed to read selection: {}", err);
-                return;
-            }
-        };
+        let list = config.removable(config.rust.keep_latest, list.iter().collect(), |v| {
+            (v.name.as_str(), v.path.as_path())
+        });
+        if list.is_empty() {
+            println!("No removable Rust versions.");
+            return Ok(());
+        }
+        let processes = Processes::snapshot(env);
+        let items: Vec<String> = list
+            .iter()
+            .map(|v| {
+                format!(
+                    "{} ({}){}",
+                    v.name,
+                    Utils::format_dir_size(&v.size),
+                    processes.marker(&v.path)
+                )
+            })
+            .collect();
+        let selections = env
+            .multi_select("Select toolchains to remove:", &items)
+            .map_err(CleanupError::Prompt)?;
 
         if selections.is_empty() {
             println!("No Rust versions selected.");
-            return;
+            return Ok(());
         }
 
-        for &index in &selections {
-            let select = &list[index];
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let select = &list[index];
+                Removal::new(
+                    "rust",
+                    &select.name,
+                    select.size.reclaimable,
+                    Some(&select.path),
+                    Backend::Tool("rustup"),
+                    move || Self::remove_toolchain(env, select),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
 
-            let output = Command::new("rustup")
-                .arg("uninstall")
-                .arg(&select.name)
-                .output();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ScriptedRunner;
+    use std::{
+        fs,
+        time::{SystemTime, UNIX_EPOCH},
+    };
 
-            println!("Removing {}...", select.name);
-            match output {
-                Ok(output) if output.status.success() => {
-                    println!("✓ Removed {}", select.name.green());
-                }
-                _ => println!("✗ Failed to remove {}", select.name.red()),
-            }
+    fn create_home() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_rust_test_{}", nanos));
+        for toolchain in [
+            "stable-x86_64-unknown-linux-gnu",
+            "1.75.0-x86_64-unknown-linux-gnu",
+        ] {
+            let bin = home.join(".rustup/toolchains").join(toolchain).join("bin");
+            fs::create_dir_all(&bin).expect("Failed to create toolchain");
+            fs::write(bin.join("rustc"), [0u8; 64]).expect("Failed to write rustc");
         }
+        home
+    }
+
+    #[test]
+    fn get_rusts_marks_active_toolchain_and_probes_versions() {
+        let home = create_home();
+        let env = Env::with_home(&home).runner(
+            ScriptedRunner::new()
+                .respond(
+                    "rustup show active-toolchain",
+                    "stable-x86_64-unknown-linux-gnu (default)\n",
+                )
+                .respond(
+                    "stable-x86_64-unknown-linux-gnu/bin/rustc --version",
+                    "rustc 1.80.0 (051478957 2024-07-21)",
+                )
+                .respond(
+                    "1.75.0-x86_64-unknown-linux-gnu/bin/rustc --version",
+                    "rustc 1.75.0 (82e1608df 2023-12-21)",
+                ),
+        );
+
+        let mut rusts = Rust::get_rusts(&env).expect("rust toolchains");
+        rusts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(rusts.len(), 2);
+        assert_eq!(rusts[0].name, "1.75.0-x86_64-unknown-linux-gnu");
+        assert!(!rusts[0].is_active);
+        assert_eq!(rusts[0].version.as_deref(), Some("1.75.0"));
+        assert!(rusts[1].is_active);
+        assert_eq!(rusts[1].version.as_deref(), Some("1.80.0"));
+        assert_eq!(rusts[1].size.logical, 64);
+
+        fs::remove_dir_all(&home).expect("Failed to remove temp directory");
     }
 }
@@ -0,0 +1,120 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::commands::Utils;
+
+/// 扫描过程中产生的进度事件
+pub enum ScanEvent<'a> {
+    /// 新发现了若干个待扫描的条目
+    Discovered(u64),
+    /// 统计到的字节数
+    Bytes(u64),
+    /// 正在遍历的目录
+    Entered(&'a Path),
+    /// 一个条目扫描完成
+    Completed,
+}
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// 扫描进度展示与 Ctrl-C 取消
+pub struct Scan;
+
+impl Scan {
+    /// 安装 Ctrl-C 处理：第一次按下取消正在进行的扫描，第二次立即退出
+    pub fn install_interrupt_handler() {
+        let result = ctrlc::set_handler(|| {
+            if CANCELLED.swap(true, Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+            Self::println("⏹  Cancelling scan, press Ctrl-C again to quit immediately...");
+        });
+        if let Err(err) = result {
+            eprintln!("Failed to install Ctrl-C handler: {}", err);
+        }
+    }
+
+    /// 扫描是否已被用户取消
+    pub fn is_cancelled() -> bool {
+        CANCELLED.load(Ordering::Relaxed)
+    }
+
+    fn with_bar(f: impl FnOnce(&ProgressBar)) {
+        if let Ok(guard) = PROGRESS.lock()
+            && let Some(bar) = guard.as_ref()
+        {
+            f(bar);
+        }
+    }
+
+    /// 开始一次扫描并显示进度条
+    pub fn begin(label: &str) {
+        let bar = ProgressBar::new(0);
+        bar.set_style(
+            ProgressStyle::with_template("{spinner:.cyan} {prefix:.bold} [{pos}/{len}] {wide_msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        );
+        bar.set_prefix(label.to_string());
+        bar.enable_steady_tick(Duration::from_millis(120));
+        BYTES.store(0, Ordering::Relaxed);
+        if let Ok(mut guard) = PROGRESS.lock() {
+            if let Some(previous) = guard.take() {
+                previous.finish_and_clear();
+            }
+            *guard = Some(bar);
+        }
+    }
+
+    /// 上报扫描事件
+    pub fn emit(event: ScanEvent) {
+        match event {
+            ScanEvent::Discovered(count) => Self::with_bar(|bar| bar.inc_length(count)),
+            ScanEvent::Bytes(bytes) => {
+                BYTES.fetch_add(bytes, Ordering::Relaxed);
+            }
+            ScanEvent::Entered(path) => Self::with_bar(|bar| {
+                bar.set_message(format!(
+                    "{} · {}",
+                    Utils::format_size(BYTES.load(Ordering::Relaxed)),
+                    path.display()
+                ))
+            }),
+            ScanEvent::Completed => Self::with_bar(|bar| bar.inc(1)),
+        }
+    }
+
+    /// 在进度条上方输出一行，使列表可以边扫描边展示
+    pub fn println(line: impl AsRef<str>) {
+        let mut printed = false;
+        Self::with_bar(|bar| {
+            if !bar.is_hidden() {
+                bar.println(line.as_ref());
+                printed = true;
+            }
+        });
+        if !printed {
+            println!("{}", line.as_ref());
+        }
+    }
+
+    /// 结束扫描并清除进度条
+    pub fn finish() {
+        if let Ok(mut guard) = PROGRESS.lock()
+            && let Some(bar) = guard.take()
+        {
+            bar.finish_and_clear();
+        }
+        if Self::is_cancelled() {
+            eprintln!("Scan cancelled; results are incomplete.");
+        }
+    }
+}
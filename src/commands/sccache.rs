@@ -0,0 +1,328 @@
+use crate::commands::{
+    Audit, AuditEntry, Backend, CleanupError, DirSize, Env, LastUse, Removal, Removals, Result,
+    Scan, ScanEvent, Utils,
+};
+use colored::Colorize;
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use walkdir::WalkDir;
+
+pub struct Sccache;
+
+/// 缓存中的一个条目（一个编译结果文件）
+#[derive(Debug, Clone)]
+pub struct SccacheEntry {
+    pub path: PathBuf,
+    pub size: DirSize,
+    pub last_use: LastUse,
+}
+
+impl SccacheEntry {
+    /// 最近一次写入或命中的时间；未知时为 0
+    fn latest(&self) -> u64 {
+        self.last_use.modified.max(self.last_use.accessed)
+    }
+
+    /// 裁剪顺序：最久未使用的在前，时间未知的排在最后
+    fn lru_key(&self) -> u64 {
+        match self.latest() {
+            0 => u64::MAX,
+            latest => latest,
+        }
+    }
+}
+
+/// 裁剪条件：两者都给出时先删过期条目，再按最久未使用的顺序删到不超过大小上限
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimLimits {
+    pub max_size: Option<u64>,
+    pub older_than: Option<Duration>,
+}
+
+/// 年龄分布的分段上限（天）
+const AGE_BUCKETS: [(u64, &str); 4] = [
+    (1, "< 1 day"),
+    (7, "< 1 week"),
+    (30, "< 1 month"),
+    (u64::MAX, "older"),
+];
+
+impl Sccache {
+    /// 缓存目录：配置的 `root`，其次 `$SCCACHE_DIR`，最后是 sccache 的平台默认位置
+    pub fn root(env: &Env) -> Result<PathBuf> {
+        let root = &env.settings().sccache.root;
+        if !root.as_os_str().is_empty() {
+            return Ok(root.clone());
+        }
+        if let Some(dir) = env.var("SCCACHE_DIR").filter(|dir| !dir.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
+        let home = env.home()?;
+        Ok(if cfg!(target_os = "macos") {
+            home.join("Library/Caches/Mozilla.sccache")
+        } else {
+            match env.var("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+                Some(dir) => PathBuf::from(dir).join("sccache"),
+                None => home.join(".cache/sccache"),
+            }
+        })
+    }
+
+    /// 读取缓存目录下的所有条目；目录不存在时为空
+    pub fn get_entries(env: &Env) -> Result<Vec<SccacheEntry>> {
+        let root = Self::root(env)?;
+        if !root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for entry in WalkDir::new(&root)
+            .into_iter()
+            .take_while(|_| !Scan::is_cancelled())
+            .filter_map(|entry| entry.ok())
+        {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            Scan::emit(ScanEvent::Bytes(metadata.len()));
+            #[cfg(unix)]
+            let size = {
+                use std::os::unix::fs::MetadataExt;
+                let allocated = metadata.blocks() * 512;
+                DirSize {
+                    logical: metadata.len(),
+                    allocated,
+                    reclaimable: allocated,
+                }
+            };
+            #[cfg(not(unix))]
+            let size = DirSize::from_logical(metadata.len());
+            entries.push(SccacheEntry {
+                path: entry.into_path(),
+                size,
+                last_use: LastUse::of(&metadata),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// 按裁剪条件挑出要删除的条目，最久未使用的在前
+    fn plan(
+        mut entries: Vec<SccacheEntry>,
+        limits: TrimLimits,
+        now: SystemTime,
+    ) -> Vec<SccacheEntry> {
+        entries.sort_by_key(SccacheEntry::lru_key);
+        let mut remaining: u64 = entries.iter().map(|e| e.size.reclaimable).sum();
+        let max_size = limits.max_size.unwrap_or(u64::MAX);
+        entries
+            .into_iter()
+            .take_while(|entry| {
+                let expired = limits
+                    .older_than
+                    .zip(entry.last_use.idle_for(now))
+                    .is_some_and(|(limit, idle)| idle >= limit);
+                let remove = expired || remaining > max_size;
+                if remove {
+                    remaining -= entry.size.reclaimable;
+                }
+                remove
+            })
+            .collect()
+    }
+
+    /// 显示缓存目录的大小与条目的年龄分布
+    pub fn show_cache(env: &Env) -> Result<()> {
+        println!("{}", "🗃️  sccache:".bold().cyan());
+        println!();
+        if !env.settings().sccache.enabled {
+            println!("sccache is disabled in config.");
+            return Ok(());
+        }
+        let root = Self::root(env)?;
+        Scan::begin("sccache");
+        let entries = Self::get_entries(env);
+        Scan::finish();
+        let entries = entries?;
+        println!("Cache directory: {}", root.display());
+        if entries.is_empty() {
+            println!("No cached entries found.");
+            return Ok(());
+        }
+
+        let total: DirSize = entries.iter().map(|e| e.size).sum();
+        println!(
+            "{} entries, {}",
+            entries.len(),
+            Utils::format_dir_size(&total).yellow()
+        );
+        let (oldest, newest) = entries
+            .iter()
+            .map(SccacheEntry::latest)
+            .filter(|&latest| latest > 0)
+            .fold((u64::MAX, 0), |(oldest, newest), latest| {
+                (oldest.min(latest), newest.max(latest))
+            });
+        if newest > 0 {
+            println!(
+                "Last used between {} and {}",
+                Utils::format_date(oldest),
+                Utils::format_date(newest)
+            );
+        }
+
+        println!();
+        let now = SystemTime::now();
+        let mut buckets = [(0usize, 0u64); AGE_BUCKETS.len()];
+        for entry in &entries {
+            let days = entry
+                .last_use
+                .idle_for(now)
+                .map_or(u64::MAX - 1, |idle| idle.as_secs() / 86_400);
+            let index = AGE_BUCKETS
+                .iter()
+                .position(|(limit, _)| days < *limit)
+                .unwrap_or(AGE_BUCKETS.len() - 1);
+            buckets[index].0 += 1;
+            buckets[index].1 += entry.size.reclaimable;
+        }
+        for ((_, label), (count, size)) in AGE_BUCKETS.iter().zip(buckets) {
+            println!(
+                "   {:<10} {:>8} entries {:>10}",
+                label,
+                count,
+                Utils::format_size(size).yellow()
+            );
+        }
+        Ok(())
+    }
+
+    /// 删除选中的条目；无需 sccache 服务运行，运行中的服务只会把缺失的条目当作未命中
+    pub(crate) fn remove_entries(env: &Env, entries: &[SccacheEntry], size: u64) -> Result<()> {
+        let root = Self::root(env)?;
+        let entry =
+            AuditEntry::new("sccache", &format!("{} entries", entries.len()), size).path(&root);
+        Audit::track(env, entry, || {
+            for cached in entries {
+                match fs::remove_file(&cached.path) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                        return Err(CleanupError::io(&cached.path, err));
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 按大小或年龄上限裁剪缓存；`yes` 为 true 时跳过确认
+    pub fn trim(env: &Env, limits: TrimLimits, yes: bool) -> Result<()> {
+        println!("{}", "🧹 Trimming sccache:".bold().cyan());
+        println!();
+        if !env.settings().sccache.enabled {
+            println!("sccache is disabled in config.");
+            return Ok(());
+        }
+        Scan::begin("sccache");
+        let entries = Self::get_entries(env);
+        Scan::finish();
+        let entries = entries?;
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        let total: u64 = entries.iter().map(|e| e.size.reclaimable).sum();
+        let plan = Self::plan(entries, limits, SystemTime::now());
+        if plan.is_empty() {
+            println!("Cache is within limits; nothing to trim.");
+            return Ok(());
+        }
+
+        let size: u64 = plan.iter().map(|e| e.size.reclaimable).sum();
+        let newest = plan.last().map_or(0, SccacheEntry::latest);
+        println!(
+            "{} entries, {} (of {}), last used before {}",
+            plan.len(),
+            Utils::format_size(size).yellow(),
+            Utils::format_size(total),
+            Utils::format_date(newest)
+        );
+        let prompt = format!(
+            "Remove {} cache entries to free {}?",
+            plan.len(),
+            Utils::format_size(size)
+        );
+        if !yes && !env.confirm(&prompt).map_err(CleanupError::Prompt)? {
+            println!("Nothing removed.");
+            return Ok(());
+        }
+
+        let item = format!("{} entries", plan.len());
+        let removal = Removal::new("sccache", &item, size, None, Backend::Filesystem, || {
+            Self::remove_entries(env, &plan, size)
+        });
+        Removals::execute(env, vec![removal]).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn entry(name: &str, days_ago: u64, size: u64, now: u64) -> SccacheEntry {
+        SccacheEntry {
+            path: PathBuf::from(name),
+            size: DirSize::from_logical(size),
+            last_use: LastUse {
+                modified: now - days_ago * 86_400,
+                accessed: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn plan_drops_expired_then_least_recently_used_entries() {
+        let now = SystemTime::now();
+        let secs = now.duration_since(UNIX_EPOCH).expect("time").as_secs();
+        let entries = vec![
+            entry("fresh", 0, 40, secs),
+            entry("week", 7, 30, secs),
+            entry("ancient", 90, 10, secs),
+            entry("month", 40, 20, secs),
+        ];
+        let names = |plan: Vec<SccacheEntry>| -> Vec<String> {
+            plan.into_iter()
+                .map(|e| e.path.display().to_string())
+                .collect()
+        };
+
+        let by_age = TrimLimits {
+            older_than: Some(Duration::from_secs(30 * 86_400)),
+            ..TrimLimits::default()
+        };
+        assert_eq!(
+            names(Sccache::plan(entries.clone(), by_age, now)),
+            ["ancient", "month"]
+        );
+
+        let by_size = TrimLimits {
+            max_size: Some(50),
+            ..TrimLimits::default()
+        };
+        assert_eq!(
+            names(Sccache::plan(entries.clone(), by_size, now)),
+            ["ancient", "month", "week"]
+        );
+
+        let within = TrimLimits {
+            max_size: Some(100),
+            older_than: Some(Duration::from_secs(365 * 86_400)),
+        };
+        assert!(Sccache::plan(entries, within, now).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! 测试用的命令执行器与交互方式，只在测试或启用 `test-support` 特性时编译
+
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::commands::{CommandOutput, CommandRunner, ProbeError, Prompter};
+
+/// 按预设输出响应命令的执行器，并记录所有调用
+#[derive(Default)]
+pub struct ScriptedRunner {
+    responses: Vec<(String, Result<String, String>)>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl ScriptedRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 命令行包含 `pattern` 时返回 `stdout`
+    pub fn respond(mut self, pattern: &str, stdout: &str) -> Self {
+        self.responses
+            .push((pattern.to_string(), Ok(stdout.to_string())));
+        self
+    }
+
+    /// 命令行包含 `pattern` 时以失败退出并输出 `stderr`
+    pub fn fail(mut self, pattern: &str, stderr: &str) -> Self {
+        self.responses
+            .push((pattern.to_string(), Err(stderr.to_string())));
+        self
+    }
+
+    /// 已执行过的命令行
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+}
+
+impl CommandRunner for ScriptedRunner {
+    fn run(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        _timeout: Duration,
+    ) -> Result<CommandOutput, ProbeError> {
+        let line = std::iter::once(program)
+            .chain(args.iter().map(|a| a.as_os_str()))
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(line.clone());
+        }
+
+        match self
+            .responses
+            .iter()
+            .find(|(pattern, _)| line.contains(pattern.as_str()))
+        {
+            Some((_, Ok(stdout))) => Ok(CommandOutput {
+                stdout: stdout.clone().into_bytes(),
+            }),
+            Some((_, Err(stderr))) => Err(ProbeError::Failed {
+                code: Some(1),
+                stderr: stderr.clone(),
+            }),
+            None => Err(ProbeError::NotFound),
+        }
+    }
+
+    fn exists(&self, program: &str) -> bool {
+        self.responses
+            .iter()
+            .any(|(pattern, _)| pattern.split_whitespace().next() == Some(program))
+    }
+}
+
+/// 按预设结果回答交互式选择
+#[derive(Default)]
+pub struct ScriptedPrompter {
+    /// `None` 表示直接接受默认勾选
+    selections: Mutex<Vec<Option<Vec<usize>>>>,
+    confirmations: Mutex<Vec<bool>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 依次作为每次多选的结果
+    pub fn select(self, indices: &[usize]) -> Self {
+        if let Ok(mut selections) = self.selections.lock() {
+            selections.push(Some(indices.to_vec()));
+        }
+        self
+    }
+
+    /// 下一次多选直接接受默认勾选的项
+    pub fn accept_defaults(self) -> Self {
+        if let Ok(mut selections) = self.selections.lock() {
+            selections.push(None);
+        }
+        self
+    }
+
+    /// 依次作为每次确认的结果
+    pub fn confirm(self, answer: bool) -> Self {
+        if let Ok(mut confirmations) = self.confirmations.lock() {
+            confirmations.push(answer);
+        }
+        self
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn multi_select(&self, prompt: &str, items: &[String]) -> io::Result<Vec<usize>> {
+        self.multi_select_with_defaults(prompt, items, &[])
+    }
+
+    fn multi_select_with_defaults(
+        &self,
+        _prompt: &str,
+        _items: &[String],
+        defaults: &[bool],
+    ) -> io::Result<Vec<usize>> {
+        let mut selections = self
+            .selections
+            .lock()
+            .map_err(|_| io::Error::other("poisoned"))?;
+        if selections.is_empty() {
+            return Err(io::Error::other("no scripted selection left"));
+        }
+        Ok(selections
+            .remove(0)
+            .unwrap_or_else(|| (0..defaults.len()).filter(|&i| defaults[i]).collect()))
+    }
+
+    fn confirm(&self, _prompt: &str) -> io::Result<bool> {
+        let mut confirmations = self
+            .confirmations
+            .lock()
+            .map_err(|_| io::Error::other("poisoned"))?;
+        if confirmations.is_empty() {
+            return Err(io::Error::other("no scripted confirmation left"));
+        }
+        Ok(confirmations.remove(0))
+    }
+}
@@ -1,9 +1,15 @@
 use colored::Colorize;
-use dialoguer::{MultiSelect, theme::ColorfulTheme};
 use serde::Deserialize;
-use std::{collections::HashMap, path::PathBuf, process::Command};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::commands::Utils;
+use crate::commands::{
+    Audit, AuditEntry, Backend, Cache, CleanupError, Config, DirSize, Env, LastUse, Removal,
+    Removals, Result, Runner, Scan, ScanEvent, Utils,
+};
 use rayon::prelude::*;
 
 #[derive(Deserialize)]
@@ -34,252 +40,702 @@ struct DeviceEntry {
     #[serde(default, rename = "udid")]
     identifier: String,
     #[serde(default, rename = "dataPath")]
-    _path: String,
+    data_path: String,
     #[serde(default, rename = "isAvailable")]
     is_available: bool,
     #[serde(default, rename = "dataPathSize")]
     size: u64,
+    #[serde(default, rename = "deviceTypeIdentifier")]
+    device_type: String,
 }
 
 #[derive(Debug)]
 pub struct SimulatorInfo {
-    name: String,
-    identifier: String,
-    version: Option<String>,
-    pub size: u64,
+    pub name: String,
+    pub identifier: String,
+    pub version: Option<String>,
+    pub size: DirSize,
+    pub last_use: LastUse,
     pub is_available: bool,
-    simulator_type: String,
+    /// "runtime"、"device" 或 "cache"
+    pub simulator_type: String,
+    /// 缓存与日志目录的路径
+    pub path: Option<PathBuf>,
+}
+
+/// 设备类型节点，包含该类型下的所有设备
+#[derive(Debug)]
+pub struct DeviceTypeNode {
+    name: String,
+    devices: Vec<SimulatorInfo>,
+}
+
+impl DeviceTypeNode {
+    pub fn size(&self) -> DirSize {
+        self.devices.iter().map(|d| d.size).sum()
+    }
+}
+
+/// 运行时节点；`runtime` 为 `None` 时表示运行时缺失的孤立设备
+#[derive(Debug)]
+pub struct RuntimeNode {
+    name: String,
+    runtime: Option<SimulatorInfo>,
+    device_types: Vec<DeviceTypeNode>,
+}
+
+impl RuntimeNode {
+    pub fn size(&self) -> DirSize {
+        self.runtime.as_ref().map(|r| r.size).unwrap_or_default()
+            + self.device_types.iter().map(|t| t.size()).sum::<DirSize>()
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.device_types.iter().map(|t| t.devices.len()).sum()
+    }
+}
+
+/// runtime -> device type -> device 的树形结构
+#[derive(Debug)]
+pub struct SimulatorTree {
+    runtimes: Vec<RuntimeNode>,
+    orphaned: Option<RuntimeNode>,
+    caches: Vec<SimulatorInfo>,
+}
+
+impl SimulatorTree {
+    pub fn size(&self) -> DirSize {
+        self.nodes().map(|n| n.size()).sum::<DirSize>()
+            + self.caches.iter().map(|c| c.size).sum::<DirSize>()
+    }
+
+    /// 所有运行时节点，孤立设备节点排在最后
+    fn nodes(&self) -> impl Iterator<Item = &RuntimeNode> {
+        self.runtimes.iter().chain(self.orphaned.iter())
+    }
+
+    /// 仍有设备使用的运行时标识符
+    pub fn referenced_runtimes(&self) -> Vec<String> {
+        self.runtimes
+            .iter()
+            .filter(|node| node.device_count() > 0)
+            .filter_map(|node| node.runtime.as_ref())
+            .map(|runtime| runtime.identifier.clone())
+            .collect()
+    }
+
+    /// 按树的先序顺序展开为扁平列表
+    pub fn into_items(self) -> Vec<SimulatorInfo> {
+        let mut items = Vec::new();
+        for node in self.runtimes.into_iter().chain(self.orphaned) {
+            items.extend(node.runtime);
+            for device_type in node.device_types {
+                items.extend(device_type.devices);
+            }
+        }
+        items.extend(self.caches);
+        items
+    }
+}
+
+impl SimulatorInfo {
+    /// 是否为 CoreSimulator 缓存或日志目录
+    pub fn is_cache(&self) -> bool {
+        self.simulator_type == "cache"
+    }
 }
 
 pub struct Simulator;
 
 impl Simulator {
-    /// 获取所有 Simulator 设备
-    fn get_simulator_devices() -> HashMap<String, Vec<DeviceEntry>> {
-        if !Utils::command_exists("xcrun") {
-            eprintln!("xcrun not found in PATH. Skipping simulator device discovery.");
-            return HashMap::new();
+    /// 执行 `xcrun simctl list <kind> -j` 并解析 JSON 输出
+    fn simctl_list<T: serde::de::DeserializeOwned>(env: &Env, kind: &str) -> Result<T> {
+        if !env.command_exists("xcrun") {
+            return Err(CleanupError::MissingTool("xcrun".to_string()));
         }
-        let output = match Command::new("xcrun")
-            .args(["simctl", "list", "devices", "-j"])
-            .output()
-        {
-            Ok(output) => output,
-            Err(err) => {
-                eprintln!("Failed to execute xcrun simctl list devices -j: {}", err);
-                return HashMap::new();
-            }
-        };
+        let args = ["simctl", "list", kind, "-j"];
+        let output = env
+            .run("xcrun", args, Runner::probe_timeout())
+            .map_err(|err| CleanupError::command(&format!("xcrun {}", args.join(" ")), err))?;
 
-        match serde_json::from_slice::<DeviceList>(&output.stdout) {
-            Ok(parsed) => parsed.devices,
-            Err(err) => {
-                eprintln!("Failed to parse devices JSON: {}", err);
-                HashMap::new()
-            }
-        }
+        serde_json::from_slice::<T>(&output.stdout).map_err(|err| CleanupError::Parse {
+            what: format!("simctl {} JSON", kind),
+            message: err.to_string(),
+        })
+    }
+
+    /// 获取所有 Simulator 设备
+    fn get_simulator_devices(env: &Env) -> Result<HashMap<String, Vec<DeviceEntry>>> {
+        Self::simctl_list::<DeviceList>(env, "devices").map(|parsed| parsed.devices)
     }
 
     /// 获取所有 Simulator 运行时和设备信息
-    fn get_simulator_runtimes() -> Vec<RuntimeEntry> {
-        if !Utils::command_exists("xcrun") {
-            eprintln!("xcrun not found in PATH. Skipping simulator runtime discovery.");
-            return Vec::new();
-        }
-        let output = match Command::new("xcrun")
-            .args(["simctl", "list", "runtimes", "-j"])
-            .output()
-        {
-            Ok(output) => output,
-            Err(err) => {
-                eprintln!("Failed to execute xcrun simctl list runtimes -j: {}", err);
-                return Vec::new();
-            }
+    fn get_simulator_runtimes(env: &Env) -> Result<Vec<RuntimeEntry>> {
+        Self::simctl_list::<RuntimeList>(env, "runtimes").map(|parsed| parsed.runtimes)
+    }
+
+    /// 将运行时与设备组织为 runtime -> device type -> device 的树，
+    /// 每个运行时节点统计完成后调用 `on_node`
+    fn build_tree(
+        runtimes: Vec<RuntimeEntry>,
+        mut devices: HashMap<String, Vec<DeviceEntry>>,
+        on_node: impl Fn(&RuntimeNode) + Sync,
+    ) -> SimulatorTree {
+        let runtimes: Vec<(RuntimeEntry, Vec<DeviceEntry>)> = runtimes
+            .into_iter()
+            .map(|runtime| {
+                let runtime_devices = devices.remove(&runtime.identifier).unwrap_or_default();
+                (runtime, runtime_devices)
+            })
+            .collect();
+        Scan::emit(ScanEvent::Discovered(runtimes.len() as u64));
+
+        let nodes: Vec<RuntimeNode> = runtimes
+            .into_par_iter()
+            .filter(|_| !Scan::is_cancelled())
+            .map(|(runtime, runtime_devices)| {
+                let (size, last_use) = Cache::measure_dir(PathBuf::from(&runtime.path).as_path());
+                let node = RuntimeNode {
+                    runtime: Some(SimulatorInfo {
+                        name: runtime.name.clone(),
+                        identifier: runtime.identifier.clone(),
+                        version: runtime.version,
+                        size,
+                        last_use,
+                        is_available: runtime.is_available,
+                        simulator_type: "runtime".to_string(),
+                        path: None,
+                    }),
+                    name: runtime.name,
+                    device_types: Self::group_by_device_type(runtime_devices),
+                };
+                Scan::emit(ScanEvent::Completed);
+                on_node(&node);
+                node
+            })
+            .collect();
+
+        let mut orphan_keys: Vec<String> = devices.keys().cloned().collect();
+        orphan_keys.sort();
+        let orphan_devices: Vec<DeviceEntry> = orphan_keys
+            .into_iter()
+            .filter_map(|key| devices.remove(&key))
+            .flatten()
+            .collect();
+
+        let orphaned = if orphan_devices.is_empty() {
+            None
+        } else {
+            Some(RuntimeNode {
+                name: "Orphaned devices".to_string(),
+                runtime: None,
+                device_types: Self::group_by_device_type(orphan_devices),
+            })
         };
+        if let Some(node) = orphaned.as_ref() {
+            on_node(node);
+        }
 
-        match serde_json::from_slice::<RuntimeList>(&output.stdout) {
-            Ok(parsed) => parsed.runtimes,
-            Err(err) => {
-                eprintln!("Failed to parse runtimes JSON: {}", err);
-                Vec::new()
+        SimulatorTree {
+            runtimes: nodes,
+            orphaned,
+            caches: Vec::new(),
+        }
+    }
+
+    /// 按设备类型对设备分组，保持首次出现的顺序
+    fn group_by_device_type(devices: Vec<DeviceEntry>) -> Vec<DeviceTypeNode> {
+        let mut groups: Vec<DeviceTypeNode> = Vec::new();
+        for device in devices {
+            let name = Self::device_type_name(&device);
+            let data_path = Path::new(&device.data_path);
+            let (size, last_use) = if !device.data_path.is_empty() && data_path.exists() {
+                Cache::measure_dir(data_path)
+            } else {
+                (DirSize::from_logical(device.size), LastUse::default())
+            };
+            let info = SimulatorInfo {
+                name: device.name,
+                identifier: device.identifier,
+                version: Some("".to_string()),
+                size,
+                last_use,
+                is_available: device.is_available,
+                simulator_type: "device".to_string(),
+                path: None,
+            };
+            match groups.iter_mut().find(|group| group.name == name) {
+                Some(group) => group.devices.push(info),
+                None => groups.push(DeviceTypeNode {
+                    name,
+                    devices: vec![info],
+                }),
             }
         }
+        groups
     }
 
-    pub fn get_simulators() -> Vec<SimulatorInfo> {
-        let runtimes = Self::get_simulator_runtimes();
-        let devices = Self::get_simulator_devices();
+    /// 从 deviceTypeIdentifier 中提取可读的设备类型名称
+    fn device_type_name(device: &DeviceEntry) -> String {
+        device
+            .device_type
+            .rsplit('.')
+            .next()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.replace('-', " "))
+            .unwrap_or_else(|| "Unknown device type".to_string())
+    }
 
-        let group: Vec<Vec<SimulatorInfo>> = runtimes
-            .into_par_iter()
-            .map(|runtime| {
-                let mut simulators: Vec<SimulatorInfo> = Vec::new();
+    /// 获取完整的 Simulator 树，包括缓存与日志目录
+    pub fn get_simulator_tree(
+        env: &Env,
+        on_node: impl Fn(&RuntimeNode) + Sync,
+    ) -> Result<SimulatorTree> {
+        let filter = &env.settings().filter;
+        let mut devices = Self::get_simulator_devices(env)?;
+        for list in devices.values_mut() {
+            list.retain(|device| {
+                filter.matches(&device.name) || filter.matches(&device.identifier)
+            });
+        }
+        devices.retain(|_, list| !list.is_empty());
+        // 运行时本身不匹配但包含匹配的设备时保留，用于展示设备所在的运行时
+        let runtimes: Vec<RuntimeEntry> = Self::get_simulator_runtimes(env)?
+            .into_iter()
+            .filter(|runtime| {
+                filter.matches(&runtime.name) || devices.contains_key(&runtime.identifier)
+            })
+            .collect();
 
-                let runtime_size =
-                    Utils::calculate_dir_size(PathBuf::from(&runtime.path).as_path());
-                let simulator = SimulatorInfo {
-                    name: runtime.name,
-                    identifier: runtime.identifier.clone(),
-                    version: runtime.version,
-                    size: runtime_size,
-                    is_available: runtime.is_available,
-                    simulator_type: "runtime".to_string(),
-                };
-                simulators.push(simulator);
+        let mut tree = Self::build_tree(runtimes, devices, on_node);
+        tree.caches = Self::get_simulator_caches(env)?;
+        Ok(tree)
+    }
 
-                let runtime_devices: Vec<DeviceEntry> = devices
-                    .get(&runtime.identifier)
-                    .cloned()
-                    .unwrap_or_default();
-                for runtime_device in runtime_devices.into_iter() {
-                    let simulator = SimulatorInfo {
-                        name: runtime_device.name,
-                        identifier: runtime_device.identifier,
-                        version: Some("".to_string()),
-                        size: runtime_device.size,
-                        is_available: runtime_device.is_available,
-                        simulator_type: "device".to_string(),
-                    };
-                    simulators.push(simulator);
+    pub fn get_simulators(env: &Env) -> Result<Vec<SimulatorInfo>> {
+        Self::get_simulator_tree(env, |_| {}).map(SimulatorTree::into_items)
+    }
+
+    /// 获取 CoreSimulator 的缓存与日志目录，它们独立于设备数据增长
+    fn get_simulator_caches(env: &Env) -> Result<Vec<SimulatorInfo>> {
+        env.home()?;
+
+        let caches = env
+            .settings()
+            .simulator
+            .caches
+            .clone()
+            .into_par_iter()
+            .filter(|path| path.exists())
+            .filter(|path| env.settings().filter.matches(&Self::cache_name(path)))
+            .map(|path| {
+                let (size, last_use) = Cache::measure_dir(&path);
+                SimulatorInfo {
+                    name: Self::cache_name(&path),
+                    identifier: path.to_string_lossy().to_string(),
+                    version: None,
+                    size,
+                    last_use,
+                    is_available: true,
+                    simulator_type: "cache".to_string(),
+                    path: Some(path),
                 }
-                simulators
             })
             .collect();
-
-        group.into_iter().flatten().collect()
+        Ok(caches)
     }
 
-    pub fn show_simulator_versions() {
-        println!("{}", "📱 iOS Simulators:".bold().cyan());
-        println!();
-        let simulators = Self::get_simulators();
-        if simulators.is_empty() {
-            println!("No iOS simulators found.");
-            return;
+    /// 缓存目录的显示名称，内置目录使用固定名称
+    fn cache_name(path: &Path) -> String {
+        if path.ends_with("CoreSimulator/Caches") {
+            "CoreSimulator Caches".to_string()
+        } else if path.ends_with("Logs/CoreSimulator") {
+            "CoreSimulator Logs".to_string()
+        } else {
+            path.display().to_string()
         }
-        let mut total_size = 0;
-        for simulator in &simulators {
-            total_size += simulator.size;
-            let status = if simulator.is_available {
-                "  available".green()
+    }
+
+    /// 清空缓存目录中的内容，保留目录本身；某一项删除失败时继续删除其余各项，并报告所有失败的条目
+    fn clear_dir_contents(dir: &Path) -> Result<()> {
+        let entries = fs::read_dir(dir).map_err(|err| CleanupError::io(dir, err))?;
+        let mut attempted = 0;
+        let mut failures = Vec::new();
+        for entry in entries {
+            attempted += 1;
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => {
+                    failures.push((dir.display().to_string(), err.to_string()));
+                    continue;
+                }
+            };
+            let removed = if path.is_dir() && !path.is_symlink() {
+                fs::remove_dir_all(&path)
             } else {
-                "  unavailable".red()
+                fs::remove_file(&path)
             };
+            if let Err(err) = removed {
+                failures.push((path.display().to_string(), err.to_string()));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(CleanupError::PartialDeletion {
+                attempted,
+                failures,
+            })
+        }
+    }
+
+    /// 可用状态标签
+    fn availability(simulator: &SimulatorInfo) -> colored::ColoredString {
+        if simulator.is_available {
+            "available".green()
+        } else {
+            "unavailable".red()
+        }
+    }
 
-            let simulator_info = simulator
-                .version
-                .as_ref()
-                .map(|v| {
-                    if v.is_empty() {
-                        "".to_string()
-                    } else {
-                        format!(" ({})", v)
-                    }
-                })
-                .unwrap_or_default();
-
-            let name_block = format!("{}{}", simulator.name, simulator_info);
-            let size_raw = Utils::format_size(simulator.size);
-            let size_colored = format!("{:>10}", size_raw).yellow();
-            if simulator.simulator_type == "runtime" {
-                println!(" {:<52} {:>10} {}", name_block, size_colored, status);
+    /// 受保护的模拟器按名称或标识符匹配
+    fn lock_marker(config: &Config, simulator: &SimulatorInfo) -> &'static str {
+        if config.is_protected(&simulator.identifier) {
+            config.lock_marker(&simulator.identifier)
+        } else {
+            config.lock_marker(&simulator.name)
+        }
+    }
+
+    /// 格式化一个运行时节点及其设备类型、设备
+    fn format_node(config: &Config, node: &RuntimeNode) -> String {
+        let mut lines = Vec::new();
+        let (title, status) = match node.runtime.as_ref() {
+            Some(runtime) => {
+                let version = runtime
+                    .version
+                    .as_ref()
+                    .map(|v| format!(" ({})", v))
+                    .unwrap_or_default();
+                (
+                    format!(
+                        "{}{}{}",
+                        node.name,
+                        version,
+                        Self::lock_marker(config, runtime)
+                    ),
+                    Self::availability(runtime),
+                )
+            }
+            None => (node.name.clone(), "missing runtime".red()),
+        };
+        let age = node
+            .runtime
+            .as_ref()
+            .map(|runtime| Utils::format_age(&runtime.last_use))
+            .unwrap_or_else(|| "-".to_string());
+        lines.push(format!(
+            "{:<55} {:>10} {:>8}  {} · {} devices",
+            title.bold(),
+            Utils::format_dir_size(&node.size()).yellow(),
+            age.dimmed(),
+            status,
+            node.device_count()
+        ));
+
+        let type_count = node.device_types.len();
+        for (type_index, device_type) in node.device_types.iter().enumerate() {
+            let last_type = type_index + 1 == type_count;
+            let (branch, stem) = if last_type {
+                ("└─", "   ")
             } else {
-                println!(" - {:<50} {:>10} {}", name_block, size_colored, status);
+                ("├─", "│  ")
             };
+            lines.push(format!(
+                "{} {:<52} {:>10}  {} devices",
+                branch,
+                device_type.name,
+                Utils::format_dir_size(&device_type.size()).yellow(),
+                device_type.devices.len()
+            ));
+
+            let device_count = device_type.devices.len();
+            for (device_index, device) in device_type.devices.iter().enumerate() {
+                let leaf = if device_index + 1 == device_count {
+                    "└─"
+                } else {
+                    "├─"
+                };
+                lines.push(format!(
+                    "{}{} {:<49} {:>10} {:>8}  {}",
+                    stem,
+                    leaf,
+                    format!(
+                        "{} ({}){}",
+                        device.name,
+                        device.identifier,
+                        Self::lock_marker(config, device)
+                    ),
+                    Utils::format_dir_size(&device.size).yellow(),
+                    Utils::format_age(&device.last_use).dimmed(),
+                    Self::availability(device)
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn show_simulator_versions(env: &Env) -> Result<()> {
+        println!("{}", "📱 iOS Simulators:".bold().cyan());
+        println!();
+        if !env.settings().simulator.enabled {
+            println!("Simulators are disabled in config.");
+            return Ok(());
         }
+        Scan::begin("Simulators");
+        let tree = Self::get_simulator_tree(env, |node| {
+            Scan::println(Self::format_node(env.settings(), node))
+        });
+        Scan::finish();
+        let tree = tree?;
+        if tree.nodes().next().is_none() && tree.caches.is_empty() {
+            println!("No iOS simulators found.");
+            return Ok(());
+        }
+
+        for cache in &tree.caches {
+            println!(
+                "{:<55} {:>10} {:>8}  {}",
+                format!("{}{}", cache.name, Self::lock_marker(env.settings(), cache)).bold(),
+                Utils::format_dir_size(&cache.size).yellow(),
+                Utils::format_age(&cache.last_use).dimmed(),
+                "cache".blue()
+            );
+        }
+
+        let count = tree
+            .nodes()
+            .map(|node| node.runtime.iter().count() + node.device_count())
+            .sum::<usize>()
+            + tree.caches.len();
         println!();
         println!(
             "Total: {:?} simulators, {}",
-            simulators.len(),
-            Utils::format_size(total_size).bold()
+            count,
+            Utils::format_dir_size(&tree.size()).bold()
         );
+        Ok(())
     }
-    pub fn clean_simulators() {
+
+    /// 缓存目录直接清空，设备与运行时经由 `xcrun simctl` 删除
+    pub fn backend(simulator: &SimulatorInfo) -> Backend {
+        if simulator.path.is_some() {
+            Backend::Filesystem
+        } else {
+            Backend::Tool("xcrun")
+        }
+    }
+
+    /// 删除一个运行时或设备；缓存与日志目录只清空内容
+    pub fn remove_simulator(env: &Env, simulator: &SimulatorInfo) -> Result<()> {
+        let entry = AuditEntry::new("simulator", &simulator.name, simulator.size.reclaimable);
+        if let Some(path) = simulator.path.as_ref() {
+            return Audit::track(env, entry.path(path), || Self::clear_dir_contents(path));
+        }
+
+        let id = if simulator.simulator_type == "runtime" {
+            &simulator.name
+        } else {
+            &simulator.identifier
+        };
+        let args = ["simctl", "delete", id.as_str()];
+        let command = format!("xcrun {}", args.join(" "));
+        Audit::track(env, entry.command(command.clone()), || {
+            env.run_removal("xcrun", args)
+                .map(|_| ())
+                .map_err(|err| CleanupError::command(&command, err))
+        })
+    }
+
+    pub fn clean_simulators(env: &Env) -> Result<()> {
         println!("{}", "🧹 Cleaning Simulator:".bold().cyan());
         println!();
-        let list = Self::get_simulators();
+        let config = env.settings();
+        if !config.simulator.enabled {
+            println!("Simulators are disabled in config.");
+            return Ok(());
+        }
+        Scan::begin("Simulators");
+        let list = Self::get_simulators(env);
+        Scan::finish();
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        let list: Vec<SimulatorInfo> = list?
+            .into_iter()
+            .filter(|simulator| {
+                config.filter.matches(&simulator.name)
+                    || config.filter.matches(&simulator.identifier)
+            })
+            .filter(|simulator| config.filter.is_unused(&simulator.last_use))
+            .filter(|simulator| {
+                let protected = config.is_protected(&simulator.name)
+                    || config.is_protected(&simulator.identifier);
+                if protected {
+                    println!("🔒 Keeping {} (protected)", simulator.name.blue());
+                }
+                !protected
+            })
+            .collect();
         if list.is_empty() {
             println!("No iOS simulators found.");
-            return;
+            return Ok(());
         }
 
         println!("{}", "🧹 Cleaning Simulators:".bold().cyan());
 
-        let selections = match MultiSelect::with_theme(&ColorfulTheme::default())
-            .items(
-                list.iter()
-                    .map(|simulator| {
-                        let status = if simulator.is_available {
-                            "  available".green()
-                        } else {
-                            "  unavailable".red()
-                        };
-                        let simulator_info = simulator
-                            .version
-                            .as_ref()
-                            .map(|v| {
-                                if v.is_empty() {
-                                    "".to_string()
-                                } else {
-                                    format!(" ({})", v)
-                                }
-                            })
-                            .unwrap_or_default();
-                        let name_block = format!("{}{}", simulator.name, simulator_info);
-                        let size_raw = Utils::format_size(simulator.size);
-                        let size_colored = format!("{:>10}", size_raw).yellow();
-                        if simulator.simulator_type == "runtime" {
-                            format!("{} {:<52} {:>10} {}", "", name_block, size_colored, status)
+        let items: Vec<String> = list
+            .iter()
+            .map(|simulator| {
+                let status = if simulator.is_available {
+                    "  available".green()
+                } else {
+                    "  unavailable".red()
+                };
+                let simulator_info = simulator
+                    .version
+                    .as_ref()
+                    .map(|v| {
+                        if v.is_empty() {
+                            "".to_string()
                         } else {
-                            format!(
-                                "{} {:<50} {:>10} {}",
-                                " -", name_block, size_colored, status
-                            )
+                            format!(" ({})", v)
                         }
                     })
-                    .collect::<Vec<String>>(),
-            )
-            .interact()
-        {
-            Ok(selection) => selection,
-            Err(err) => {
-                eprintln!("Failed to read selection: {}", err);
-                return;
-            }
-        };
+                    .unwrap_or_default();
+                let name_block = format!("{}{}", simulator.name, simulator_info);
+                let size_raw = Utils::format_dir_size(&simulator.size);
+                let size_colored = format!("{:>10}", size_raw).yellow();
+                if simulator.simulator_type == "runtime" {
+                    format!("{} {:<52} {:>10} {}", "", name_block, size_colored, status)
+                } else if simulator.simulator_type == "cache" {
+                    format!(
+                        "{} {:<52} {:>10}   {}",
+                        "",
+                        name_block,
+                        size_colored,
+                        "cache".blue()
+                    )
+                } else {
+                    format!(
+                        "{} {:<50} {:>10} {}",
+                        " -", name_block, size_colored, status
+                    )
+                }
+            })
+            .collect();
+        let selections = env
+            .multi_select("Select simulators to remove:", &items)
+            .map_err(CleanupError::Prompt)?;
 
         if selections.is_empty() {
             println!("No simulator selected.");
-            return;
+            return Ok(());
         }
-        for &index in &selections {
-            let select = &list[index];
 
-            let id = if select.simulator_type == "runtime" {
-                &select.name
-            } else {
-                &select.identifier
-            };
-            let output = Command::new("xcrun")
-                .args(["simctl", "delete"])
-                .arg(id)
-                .output();
-
-            println!("Removing {}...", select.name);
-            match output {
-                Ok(output) => {
-                    if output.status.success() {
-                        println!("✓ Removed {}", select.name.green());
-                    } else {
-                        println!(
-                            "✗ Failed to remove {}: {}",
-                            select.name.red(),
-                            String::from_utf8_lossy(&output.stderr)
-                        );
-                    }
-                }
-                Err(err) => println!("✗ Failed to remove {}: {}", select.name.red(), err),
-            }
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let select = &list[index];
+                Removal::new(
+                    "simulator",
+                    &select.name,
+                    select.size.reclaimable,
+                    select.path.as_deref(),
+                    Self::backend(select),
+                    move || Self::remove_simulator(env, select),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime(name: &str, identifier: &str) -> RuntimeEntry {
+        RuntimeEntry {
+            name: name.to_string(),
+            identifier: identifier.to_string(),
+            path: String::new(),
+            is_available: true,
+            version: None,
+        }
+    }
+
+    fn device(name: &str, device_type: &str, size: u64) -> DeviceEntry {
+        DeviceEntry {
+            name: name.to_string(),
+            identifier: format!("{}-udid", name),
+            data_path: String::new(),
+            is_available: true,
+            size,
+            device_type: format!("com.apple.CoreSimulator.SimDeviceType.{}", device_type),
         }
     }
+
+    #[test]
+    fn build_tree_groups_devices_and_keeps_orphans() {
+        let ios17 = "com.apple.CoreSimulator.SimRuntime.iOS-17-0";
+        let ios15 = "com.apple.CoreSimulator.SimRuntime.iOS-15-0";
+        let mut devices = HashMap::new();
+        devices.insert(
+            ios17.to_string(),
+            vec![
+                device("iPhone 15 A", "iPhone-15", 100),
+                device("iPad Air", "iPad-Air-5th-generation", 50),
+                device("iPhone 15 B", "iPhone-15", 25),
+            ],
+        );
+        devices.insert(ios15.to_string(), vec![device("Old", "iPhone-13", 10)]);
+
+        let tree = Simulator::build_tree(vec![runtime("iOS 17.0", ios17)], devices, |_| {});
+
+        assert_eq!(tree.runtimes.len(), 1);
+        let node = &tree.runtimes[0];
+        assert_eq!(node.device_types.len(), 2);
+        assert_eq!(node.device_types[0].name, "iPhone 15");
+        assert_eq!(node.device_types[0].size().logical, 125);
+        assert_eq!(node.device_count(), 3);
+        assert_eq!(node.size().logical, 175);
+
+        let orphaned = tree.orphaned.as_ref().expect("orphaned bucket");
+        assert!(orphaned.runtime.is_none());
+        assert_eq!(orphaned.device_types[0].name, "iPhone 13");
+        assert_eq!(tree.size().logical, 185);
+        assert_eq!(tree.into_items().len(), 5);
+    }
+
+    #[test]
+    fn get_simulators_parses_simctl_json() {
+        let runtimes = r#"{"runtimes": [{"name": "iOS 17.0", "identifier": "com.apple.CoreSimulator.SimRuntime.iOS-17-0", "bundlePath": "/nonexistent/iOS 17.0.simruntime", "isAvailable": true, "version": "17.0"}]}"#;
+        let devices = r#"{"devices": {
+            "com.apple.CoreSimulator.SimRuntime.iOS-17-0": [{"name": "iPhone 15", "udid": "AAAA", "dataPath": "", "isAvailable": true, "dataPathSize": 2048, "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-15"}],
+            "com.apple.CoreSimulator.SimRuntime.iOS-16-0": [{"name": "iPhone 14", "udid": "BBBB", "isAvailable": false, "dataPathSize": 1024}]
+        }}"#;
+        let env = Env::with_home(std::env::temp_dir().join("cleanup_simulator_test_missing"))
+            .runner(
+                crate::commands::ScriptedRunner::new()
+                    .respond("xcrun simctl list runtimes -j", runtimes)
+                    .respond("xcrun simctl list devices -j", devices),
+            );
+
+        let simulators = Simulator::get_simulators(&env).expect("simulators");
+        let names: Vec<&str> = simulators.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["iOS 17.0", "iPhone 15", "iPhone 14"]);
+        assert_eq!(simulators[1].size.logical, 2048);
+        assert!(!simulators[2].is_available);
+    }
 }
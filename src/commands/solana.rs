@@ -1,187 +1,305 @@
-use crate::commands::Utils;
+use crate::commands::{
+    Audit, AuditEntry, Backend, Cache, CleanupError, Config, DirSize, Env, LastUse, Processes,
+    Removal, Removals, Result, Runner, Scan, ScanEvent, Utils,
+};
 use colored::Colorize;
-use dialoguer::{MultiSelect, theme::ColorfulTheme};
 use rayon::prelude::*;
 use std::{
     fs,
     path::{Path, PathBuf},
-    process::Command,
 };
 pub struct Solana;
 
 #[derive(Debug)]
 pub struct SolanaInfo {
-    name: String,
-    path: String,
-    pub size: u64,
-    is_active: bool,
-    version: Option<String>,
+    pub name: String,
+    pub path: PathBuf,
+    pub size: DirSize,
+    pub is_active: bool,
+    pub version: Option<String>,
+    pub last_use: LastUse,
 }
 
 impl Solana {
     /// 获取单个 Solana 版本的信息
-    fn get_solana_version_info(version_path: &Path) -> Option<String> {
+    fn get_solana_version_info(env: &Env, version_path: &Path) -> Option<String> {
         let solana_bin = version_path.join("solana-release/bin/solana");
         if !solana_bin.exists() {
             return None;
         }
-        let output = Command::new(solana_bin).arg("--version").output().ok()?;
-        String::from_utf8(output.stdout)
-            .ok()
-            .and_then(|s| s.split_whitespace().nth(1).map(|v| v.to_string()))
+        let output = env
+            .run(solana_bin, ["--version"], Runner::probe_timeout())
+            .ok()?;
+        output
+            .stdout_string()
+            .split_whitespace()
+            .nth(1)
+            .map(|v| v.to_string())
     }
 
-    /// 获取当前激活的 Solana 版本
-    fn get_active_solana_version() -> Option<String> {
-        let home = match std::env::var("HOME") {
-            Ok(path) => path,
-            Err(err) => {
-                eprintln!("HOME environment variable is not set: {}", err);
-                return None;
-            }
-        };
-        let active_link = Path::new(&home).join(".local/share/solana/install/active_release");
+    /// 获取当前激活的 Solana 版本；没有 `active_release` 链接时为空
+    fn get_active_solana_version(env: &Env) -> Result<Option<String>> {
+        let active_link = env.settings().solana.root()?.join("active_release");
 
         match fs::read_link(&active_link) {
-            Ok(target) => target
-                .to_string_lossy()
-                .split('/')
-                .find(|s| s.starts_with("stable-"))
-                .map(|s| s.to_string()),
-            Err(err) => {
-                eprintln!("Failed to read active Solana link: {}", err);
-                None
+            Ok(target) => {
+                let target = target.to_string_lossy().to_string();
+                let parts: Vec<&str> = target.split('/').collect();
+                // active_release -> .../releases/<version>/solana-release
+                Ok(parts
+                    .iter()
+                    .position(|s| *s == "releases")
+                    .and_then(|i| parts.get(i + 1))
+                    .or_else(|| parts.iter().find(|s| s.starts_with("stable-")))
+                    .map(|s| s.to_string()))
             }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(CleanupError::io(&active_link, err)),
         }
     }
 
     /// 获取所有 Solana 版本
-    pub fn get_solanas() -> Vec<SolanaInfo> {
-        let home = match std::env::var("HOME") {
-            Ok(path) => PathBuf::from(path),
-            Err(err) => {
-                eprintln!("HOME environment variable is not set: {}", err);
-                return Vec::new();
-            }
-        };
-        let solana_dir = home.join(".local/share/solana/install/releases");
+    pub fn get_solanas(env: &Env) -> Result<Vec<SolanaInfo>> {
+        Self::scan_solanas(env, |_| {})
+    }
+
+    /// 扫描所有 Solana 版本，每扫描完一个就调用 `on_item`
+    pub fn scan_solanas(
+        env: &Env,
+        on_item: impl Fn(&SolanaInfo) + Sync,
+    ) -> Result<Vec<SolanaInfo>> {
+        let solana_dir = env.settings().solana.root()?.join("releases");
 
         if !solana_dir.exists() {
-            return Vec::new();
+            return Ok(Vec::new());
         }
-        let active_version = Self::get_active_solana_version();
+        let active_version = Self::get_active_solana_version(env)?;
 
-        let entries = match std::fs::read_dir(&solana_dir) {
-            Ok(entries) => entries,
-            Err(err) => {
-                eprintln!("Failed to read Solana releases directory: {}", err);
-                return Vec::new();
-            }
-        };
+        let entries =
+            std::fs::read_dir(&solana_dir).map_err(|err| CleanupError::io(&solana_dir, err))?;
 
+        let filter = &env.settings().filter;
         let paths: Vec<PathBuf> = entries
             .into_iter()
             .filter_map(|entry| entry.ok())
             .map(|e| e.path())
+            .filter(|path| path.exists())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| filter.matches(name))
+            })
             .collect();
+        Scan::emit(ScanEvent::Discovered(paths.len() as u64));
 
-        paths
+        let solanas = paths
             .into_par_iter()
-            .filter(|path| path.exists())
+            .filter(|_| !Scan::is_cancelled())
             .filter_map(|path| {
                 let name = path.file_name()?.to_str()?.to_string();
-                let size = Utils::calculate_dir_size(&path);
-                let is_active = active_version.as_ref().is_some_and(|v| name.contains(v));
-                let version = Self::get_solana_version_info(&path);
-                Some(SolanaInfo {
+                let (size, last_use) = Cache::measure_dir(&path);
+                // 工具链二进制的访问时间最能反映最近一次使用
+                let last_use = fs::metadata(path.join("solana-release/bin/solana"))
+                    .map(|metadata| last_use.merge(LastUse::of(&metadata)))
+                    .unwrap_or(last_use);
+                if !filter.is_unused(&last_use) {
+                    Scan::emit(ScanEvent::Completed);
+                    return None;
+                }
+                let is_active = active_version.as_ref().is_some_and(|v| name == *v);
+                let version = Self::get_solana_version_info(env, &path);
+                let info = SolanaInfo {
                     name,
-                    path: path.to_string_lossy().to_string(),
+                    path,
                     size,
                     is_active,
                     version,
-                })
+                    last_use,
+                };
+                Scan::emit(ScanEvent::Completed);
+                on_item(&info);
+                Some(info)
             })
-            .collect()
+            .collect();
+        Ok(solanas)
+    }
+
+    /// 格式化单个版本的列表行
+    fn format_row(config: &Config, version: &SolanaInfo) -> String {
+        let status = if version.is_active {
+            "✓ active".green()
+        } else {
+            "  inactive".red()
+        };
+
+        let version_info = version
+            .version
+            .as_ref()
+            .map(|v| format!(" ({})", v))
+            .unwrap_or_default();
+        format!(
+            "{:<50} {:>10} {:>8} {}",
+            format!(
+                "{}{}{}",
+                version.name,
+                version_info,
+                config.lock_marker(&version.name)
+            ),
+            Utils::format_dir_size(&version.size).yellow(),
+            Utils::format_age(&version.last_use).dimmed(),
+            status
+        )
     }
 
     /// 列出 Solana 版本
-    pub fn show_solana_versions() {
+    pub fn show_solana_versions(env: &Env) -> Result<()> {
         println!("{}", "⚡ Solana Versions:".bold().cyan());
         println!();
-        let versions = Self::get_solanas();
+        if !env.settings().solana.enabled {
+            println!("Solana is disabled in config.");
+            return Ok(());
+        }
+        Scan::begin("Solana");
+        let versions = Self::scan_solanas(env, |version| {
+            Scan::println(Self::format_row(env.settings(), version))
+        });
+        Scan::finish();
+        let versions = versions?;
         if versions.is_empty() {
             println!("No Solana versions found.");
-            return;
-        }
-        let mut total_size = 0;
-        for version in &versions {
-            total_size += version.size;
-            let status = if version.is_active {
-                "✓ active".green()
-            } else {
-                "  inactive".red()
-            };
-
-            let version_info = version
-                .version
-                .as_ref()
-                .map(|v| format!(" ({})", v))
-                .unwrap_or_default();
-            println!(
-                "{:<50} {:>10} {}",
-                format!("{}{}", version.name, version_info),
-                Utils::format_size(version.size).yellow(),
-                status
-            );
+            return Ok(());
         }
+        let total_size: DirSize = versions.iter().map(|v| v.size).sum();
         println!();
         println!(
             "Total: {:?} versions, {}",
             versions.len(),
-            Utils::format_size(total_size).bold()
+            Utils::format_dir_size(&total_size).bold()
         );
+        Ok(())
+    }
+
+    /// 删除一个 Solana 版本目录
+    pub fn remove_version(env: &Env, version: &SolanaInfo) -> Result<()> {
+        let entry =
+            AuditEntry::new("solana", &version.name, version.size.reclaimable).path(&version.path);
+        Audit::track(env, entry, || {
+            fs::remove_dir_all(&version.path).map_err(|err| CleanupError::io(&version.path, err))
+        })
     }
 
     /// 清理 Solana 版本
-    pub fn clean_solana_versions() {
-        let list = Self::get_solanas();
+    pub fn clean_solana_versions(env: &Env) -> Result<()> {
+        let config = env.settings();
+        if !config.solana.enabled {
+            println!("Solana is disabled in config.");
+            return Ok(());
+        }
+        Scan::begin("Solana");
+        let list = Self::get_solanas(env);
+        Scan::finish();
+        let list = list?;
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
         let inactive_versions: Vec<&SolanaInfo> = list.iter().filter(|v| !v.is_active).collect();
         if inactive_versions.is_empty() {
             println!("No Solana versions found.");
-            return;
+            return Ok(());
         }
 
         println!("{}", "🧹 Cleaning Solana Versions:".bold().cyan());
 
-        let selections = match MultiSelect::with_theme(&ColorfulTheme::default())
-            .with_prompt("Select versions to remove:")
-            .items(
-                inactive_versions
-                    .iter()
-                    .map(|v| format!("{} ({})", v.name, Utils::format_size(v.size)))
-                    .collect::<Vec<String>>(),
-            )
-            .interact()
-        {
-            Ok(selection) => selection,
-            Err(err) => {
-                eprintln!("Failed to read selection: {}", err);
-                return;
-            }
-        };
+        let inactive_versions =
+            config.removable(config.solana.keep_latest, inactive_versions, |v| {
+                (v.name.as_str(), v.path.as_path())
+            });
+        if inactive_versions.is_empty() {
+            println!("No removable Solana versions.");
+            return Ok(());
+        }
+
+        let processes = Processes::snapshot(env);
+        let items: Vec<String> = inactive_versions
+            .iter()
+            .map(|v| {
+                format!(
+                    "{} ({}){}",
+                    v.name,
+                    Utils::format_dir_size(&v.size),
+                    processes.marker(&v.path)
+                )
+            })
+            .collect();
+        let selections = env
+            .multi_select("Select versions to remove:", &items)
+            .map_err(CleanupError::Prompt)?;
 
         if selections.is_empty() {
             println!("No Solana versions selected.");
-            return;
+            return Ok(());
         }
 
-        for &index in &selections {
-            let select = &inactive_versions[index];
-            println!("Removing {}...", select.name);
-            match fs::remove_dir_all(&select.path) {
-                Ok(_) => println!("{} removed.", select.name.green()),
-                Err(err) => println!("Failed to remove {}: {}.", select.name.red(), err),
-            }
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let select = &inactive_versions[index];
+                Removal::new(
+                    "solana",
+                    &select.name,
+                    select.size.reclaimable,
+                    Some(&select.path),
+                    Backend::Filesystem,
+                    move || Self::remove_version(env, select),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ScriptedRunner;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[cfg(unix)]
+    #[test]
+    fn get_solanas_follows_active_release_link() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_solana_test_{}", nanos));
+        let install = home.join(".local/share/solana/install");
+        for version in ["1.17.0", "1.18.4"] {
+            let bin = install
+                .join("releases")
+                .join(version)
+                .join("solana-release/bin");
+            fs::create_dir_all(&bin).expect("Failed to create release");
+            fs::write(bin.join("solana"), [0u8; 32]).expect("Failed to write solana");
         }
+        std::os::unix::fs::symlink(
+            install.join("releases/1.18.4/solana-release"),
+            install.join("active_release"),
+        )
+        .expect("Failed to link active release");
+
+        let env = Env::with_home(&home).runner(ScriptedRunner::new().respond(
+            "1.18.4/solana-release/bin/solana --version",
+            "solana-cli 1.18.4 (src:abc; feat:1)",
+        ));
+        let mut solanas = Solana::get_solanas(&env).expect("solana releases");
+        solanas.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(solanas.len(), 2);
+        assert!(!solanas[0].is_active);
+        assert_eq!(solanas[0].version, None);
+        assert!(solanas[1].is_active);
+        assert_eq!(solanas[1].version.as_deref(), Some("1.18.4"));
+
+        fs::remove_dir_all(&home).expect("Failed to remove temp directory");
     }
 }
@@ -0,0 +1,316 @@
+use crate::commands::{
+    Audit, AuditEntry, Backend, Cache, CleanupError, Config, DirSize, Env, LastUse, Process,
+    Processes, Removal, Removals, Result, Scan, ScanEvent, Utils,
+};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+pub struct SolanaLedger;
+
+/// `solana-test-validator` 或 `anchor test` 留下的账本目录
+#[derive(Debug)]
+pub struct LedgerInfo {
+    /// 如 `my-program: .anchor/test-ledger`
+    pub name: String,
+    pub path: PathBuf,
+    pub size: DirSize,
+    pub last_use: LastUse,
+    /// 仍在使用该账本的验证器进程
+    pub running: Vec<Process>,
+}
+
+impl SolanaLedger {
+    /// 账本目录中总有 `genesis.bin`、`rocksdb` 或 `validator.log` 之一
+    fn is_ledger(path: &Path) -> bool {
+        path.join("genesis.bin").is_file()
+            || path.join("rocksdb").is_dir()
+            || path.join("validator.log").exists()
+    }
+
+    /// 在根目录下查找 `test-ledger`；除 `.anchor` 外不进入隐藏目录
+    fn find(roots: &[PathBuf], depth: usize) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for root in roots {
+            let mut walker = WalkDir::new(root).max_depth(depth).into_iter();
+            while let Some(entry) = walker.next() {
+                if Scan::is_cancelled() {
+                    break;
+                }
+                let Ok(entry) = entry else {
+                    continue;
+                };
+                if !entry.file_type().is_dir() || entry.depth() == 0 {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy();
+                if name.starts_with('.') && name != ".anchor" {
+                    walker.skip_current_dir();
+                    continue;
+                }
+                if name == "test-ledger" && Self::is_ledger(entry.path()) {
+                    found.push(entry.into_path());
+                    walker.skip_current_dir();
+                }
+            }
+        }
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// 以所属项目命名；`.anchor/test-ledger` 属于 `.anchor` 的上一级目录
+    fn name(path: &Path) -> String {
+        let mut project = path.parent();
+        let mut relative = "test-ledger";
+        if project
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == ".anchor")
+        {
+            project = project.and_then(Path::parent);
+            relative = ".anchor/test-ledger";
+        }
+        let project = project
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        format!("{}: {}", project, relative)
+    }
+
+    /// 要查找的根目录：命令行给出的路径，其次配置的 `roots`，最后是当前目录
+    fn roots(env: &Env, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        if !paths.is_empty() {
+            return Ok(paths.to_vec());
+        }
+        let roots = &env.settings().solana_ledger.roots;
+        if !roots.is_empty() {
+            return Ok(roots.clone());
+        }
+        let current = std::env::current_dir().map_err(|err| CleanupError::io(".", err))?;
+        Ok(vec![current])
+    }
+
+    /// 查找所有账本并标出仍被验证器使用的账本
+    pub fn get_ledgers(
+        env: &Env,
+        paths: &[PathBuf],
+        processes: &Processes,
+    ) -> Result<Vec<LedgerInfo>> {
+        let roots = Self::roots(env, paths)?;
+        let ledgers = Self::find(&roots, env.settings().solana_ledger.depth);
+        Scan::emit(ScanEvent::Discovered(ledgers.len() as u64));
+        Ok(ledgers
+            .into_par_iter()
+            .filter(|_| !Scan::is_cancelled())
+            .map(|path| {
+                let (size, last_use) = Cache::measure_dir(&path);
+                Scan::emit(ScanEvent::Completed);
+                LedgerInfo {
+                    name: Self::name(&path),
+                    running: processes.users(&path),
+                    path,
+                    size,
+                    last_use,
+                }
+            })
+            .collect())
+    }
+
+    /// 可以删除的账本：验证器已退出、未受保护，且符合本次命令的过滤条件
+    fn removable<'a>(config: &Config, ledgers: &'a [LedgerInfo]) -> Vec<&'a LedgerInfo> {
+        let candidates = ledgers
+            .iter()
+            .filter(|ledger| ledger.running.is_empty())
+            .filter(|ledger| {
+                config.filter.matches(&ledger.name) && config.filter.is_unused(&ledger.last_use)
+            })
+            .collect();
+        config.removable(0, candidates, |ledger| {
+            (ledger.name.as_str(), ledger.path.as_path())
+        })
+    }
+
+    fn format_row(ledger: &LedgerInfo) -> String {
+        let running = if ledger.running.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " ⚠ validator running: {}",
+                Processes::describe(&ledger.running)
+            )
+            .yellow()
+            .to_string()
+        };
+        let last_run = match ledger.last_use.modified {
+            0 => "unknown".to_string(),
+            modified => Utils::format_date(modified),
+        };
+        format!(
+            "{:<45} {:>10}  last run {}{}",
+            ledger.name,
+            Utils::format_dir_size(&ledger.size),
+            last_run,
+            running
+        )
+    }
+
+    fn scan(env: &Env, paths: &[PathBuf]) -> Result<Vec<LedgerInfo>> {
+        let processes = Processes::snapshot(env);
+        Scan::begin("Solana ledgers");
+        let ledgers = Self::get_ledgers(env, paths, &processes);
+        Scan::finish();
+        let ledgers = ledgers?;
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        Ok(ledgers)
+    }
+
+    /// 列出账本的大小、最近一次运行时间与仍在运行的验证器
+    pub fn show_ledgers(env: &Env, paths: &[PathBuf]) -> Result<()> {
+        println!("{}", "🧪 Solana test ledgers:".bold().cyan());
+        println!();
+        if !env.settings().solana_ledger.enabled {
+            println!("Solana ledgers are disabled in config.");
+            return Ok(());
+        }
+        let ledgers = Self::scan(env, paths)?;
+        if ledgers.is_empty() {
+            println!("No test ledgers found.");
+            return Ok(());
+        }
+        for ledger in &ledgers {
+            println!("📁 {}", Self::format_row(ledger));
+            println!("   {}", ledger.path.display().to_string().dimmed());
+        }
+        let total: DirSize = ledgers.iter().map(|ledger| ledger.size).sum();
+        println!();
+        println!(
+            "{} ledgers, {}",
+            ledgers.len(),
+            Utils::format_dir_size(&total).yellow()
+        );
+        Ok(())
+    }
+
+    pub fn remove_ledger(env: &Env, ledger: &LedgerInfo) -> Result<()> {
+        let entry = AuditEntry::new("solana-ledger", &ledger.name, ledger.size.reclaimable)
+            .path(&ledger.path);
+        Audit::track(env, entry, || {
+            fs::remove_dir_all(&ledger.path).map_err(|err| CleanupError::io(&ledger.path, err))
+        })
+    }
+
+    /// 删除选中的账本；验证器仍在运行的账本不提供删除，`--force` 也不例外
+    pub fn clean_ledgers(env: &Env, paths: &[PathBuf]) -> Result<()> {
+        println!("{}", "🧹 Cleaning Solana test ledgers:".bold().cyan());
+        println!();
+        let config = env.settings();
+        if !config.solana_ledger.enabled {
+            println!("Solana ledgers are disabled in config.");
+            return Ok(());
+        }
+        let ledgers = Self::scan(env, paths)?;
+        for ledger in ledgers.iter().filter(|ledger| !ledger.running.is_empty()) {
+            println!(
+                "{} Skipped {}: validator running: {}",
+                "✗".yellow(),
+                ledger.name,
+                Processes::describe(&ledger.running)
+            );
+        }
+        let removable = Self::removable(config, &ledgers);
+        if removable.is_empty() {
+            println!("No removable test ledgers.");
+            return Ok(());
+        }
+
+        let items: Vec<String> = removable.iter().map(|l| Self::format_row(l)).collect();
+        let selections = env
+            .multi_select("Select ledgers to remove:", &items)
+            .map_err(CleanupError::Prompt)?;
+        if selections.is_empty() {
+            println!("No ledgers selected.");
+            return Ok(());
+        }
+
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let ledger = removable[index];
+                Removal::new(
+                    "solana-ledger",
+                    &ledger.name,
+                    ledger.size.reclaimable,
+                    Some(&ledger.path),
+                    Backend::Filesystem,
+                    move || Self::remove_ledger(env, ledger),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn ledger(path: &Path) {
+        fs::create_dir_all(path.join("rocksdb")).expect("Failed to create rocksdb");
+        fs::write(path.join("genesis.bin"), [0u8; 1024]).expect("Failed to write genesis");
+        fs::write(path.join("rocksdb/000001.sst"), [0u8; 4096]).expect("Failed to write sst");
+    }
+
+    #[test]
+    fn get_ledgers_finds_project_and_anchor_ledgers() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("cleanup_ledger_test_{}", nanos));
+        ledger(&root.join("counter/test-ledger"));
+        ledger(&root.join("workspace/escrow/.anchor/test-ledger"));
+        ledger(&root.join("counter/.git/test-ledger"));
+        fs::create_dir_all(root.join("notes/test-ledger")).expect("Failed to create plain dir");
+
+        let env = Env::with_home(&root);
+        let ledgers =
+            SolanaLedger::get_ledgers(&env, std::slice::from_ref(&root), &Processes::default())
+                .expect("ledgers");
+        let mut names: Vec<&str> = ledgers.iter().map(|l| l.name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["counter: test-ledger", "escrow: .anchor/test-ledger"]
+        );
+        assert!(ledgers.iter().all(|l| l.size.logical >= 5120));
+        assert_eq!(SolanaLedger::removable(env.settings(), &ledgers).len(), 2);
+
+        #[cfg(target_os = "linux")]
+        {
+            let running = root.join("counter/test-ledger");
+            let mut validator = std::process::Command::new("sleep")
+                .arg("30")
+                .current_dir(&running)
+                .spawn()
+                .expect("Failed to spawn sleep");
+            let processes = Processes::snapshot(&env);
+            let ledgers = SolanaLedger::get_ledgers(&env, std::slice::from_ref(&root), &processes)
+                .expect("ledgers");
+            validator.kill().expect("Failed to kill sleep");
+            validator.wait().expect("Failed to wait for sleep");
+            let removable: Vec<&str> = SolanaLedger::removable(env.settings(), &ledgers)
+                .iter()
+                .map(|l| l.name.as_str())
+                .collect();
+            assert_eq!(removable, ["escrow: .anchor/test-ledger"]);
+        }
+        fs::remove_dir_all(&root).expect("Failed to remove temp directory");
+    }
+}
@@ -1,48 +1,243 @@
-use crate::commands::{Rust, Simulator, Solana, Utils};
+use crate::commands::{
+    CargoBin, CleanupError, Custom, DirSize, Disk, Env, History, Report, Result, Rust, Scan,
+    Sccache, Simulator, Snapshot, Solana, Utils, Volume,
+};
 use colored::Colorize;
+use std::path::{Path, PathBuf};
 
 pub struct Status;
 
 impl Status {
-    pub fn show_status() {
+    /// 单个类别扫描失败不影响其余类别：缺少工具只做提示，其它错误计入失败汇总
+    pub(crate) fn category<T>(label: &str, result: Result<Vec<T>>) -> Vec<T> {
+        match result {
+            Ok(items) => items,
+            Err(CleanupError::MissingTool(tool)) => {
+                eprintln!("{} {}: {} not installed, skipped", "ℹ".blue(), label, tool);
+                Vec::new()
+            }
+            Err(err) => {
+                eprintln!("{} {}: {}", "⚠".yellow(), label, err);
+                Report::record(err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 各类别扫描根目录所在的卷；读取失败的卷只做提示
+    fn volumes(roots: &[PathBuf]) -> Vec<Volume> {
+        Disk::volumes(roots.iter().map(PathBuf::as_path))
+            .into_iter()
+            .filter_map(|volume| match volume {
+                Ok(volume) => Some(volume),
+                Err(err) => {
+                    eprintln!("{} Disk: {}", "⚠".yellow(), err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// 类别占其所在卷已用空间的比例，例如 ` (12.5% of used on /)`
+    fn share(volumes: &[Volume], root: Option<&Path>, size: &DirSize) -> String {
+        if size.allocated == 0 {
+            return String::new();
+        }
+        let Some(volume) = root
+            .and_then(|root| Disk::volume(root).ok())
+            .and_then(|volume| volumes.iter().find(|v| v.device == volume.device))
+        else {
+            return String::new();
+        };
+        format!(
+            " ({:.1}% of used on {})",
+            volume.share_of_used(size.allocated),
+            volume.mount.display()
+        )
+        .dimmed()
+        .to_string()
+    }
+
+    /// 汇总各类别的占用；`record` 为 true（或配置了 `history.record`）时追加一条历史快照
+    pub fn show_status(env: &Env, record: bool) -> Result<()> {
         println!("{}", "📊 Storage Usage Summary:".bold().cyan());
         println!();
 
-        let rusts = Rust::get_rusts();
-        let solanas = Solana::get_solanas();
-        let simulators = Simulator::get_simulators();
+        env.home()?;
+        let config = env.settings();
+        Scan::begin("Scanning");
+        let rusts = config.rust.enabled.then(|| Rust::get_rusts(env));
+        let solanas = config.solana.enabled.then(|| Solana::get_solanas(env));
+        let simulators = config
+            .simulator
+            .enabled
+            .then(|| Simulator::get_simulators(env));
+        let crates = config.cargo_bin.enabled.then(|| CargoBin::get_crates(env));
+        let sccache = config.sccache.enabled.then(|| Sccache::get_entries(env));
+        let customs: Vec<_> = config
+            .custom
+            .iter()
+            .filter(|target| target.enabled)
+            .map(|target| (target, Custom::get_items(env, target)))
+            .collect();
+        Scan::finish();
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        for (label, enabled) in [
+            ("Rust", rusts.is_some()),
+            ("Solana", solanas.is_some()),
+            ("Simulator", simulators.is_some()),
+            ("Cargo", crates.is_some()),
+            ("sccache", sccache.is_some()),
+        ] {
+            if !enabled {
+                println!("{} {}: disabled in config", "ℹ".blue(), label);
+            }
+        }
+        let rusts = rusts.map_or_else(Vec::new, |r| Self::category("Rust", r));
+        let solanas = solanas.map_or_else(Vec::new, |s| Self::category("Solana", s));
+        let simulators = simulators.map_or_else(Vec::new, |s| Self::category("Simulator", s));
+        let crates = crates.map_or_else(Vec::new, |c| Self::category("Cargo", c));
+        let sccache = sccache.map_or_else(Vec::new, |s| Self::category("sccache", s));
+
+        let home = env.home()?;
+        let rust_root = config.rust.root().ok();
+        let solana_root = config.solana.root().ok();
+        let simulator_root = home.join("Library/Developer/CoreSimulator");
+        let mut roots: Vec<PathBuf> = vec![home.clone()];
+        roots.extend(rust_root.clone());
+        roots.extend(solana_root.clone());
+        let cargo_root = config.cargo_bin.root().ok();
+        roots.extend(cargo_root.clone());
+        let sccache_root = config
+            .sccache
+            .enabled
+            .then(|| Sccache::root(env).ok())
+            .flatten();
+        roots.extend(sccache_root.clone());
+        if config.simulator.enabled {
+            roots.push(simulator_root.clone());
+            roots.extend(config.simulator.caches.iter().cloned());
+        }
+        roots.extend(customs.iter().filter_map(|(target, _)| target.root().ok()));
+        let volumes = Self::volumes(&roots);
 
-        let rust_total: u64 = rusts.iter().map(|r| r.size).sum();
-        let solana_total: u64 = solanas.iter().map(|s| s.size).sum();
-        let simulator_total: u64 = simulators.iter().map(|s| s.size).sum();
+        let rust_total: DirSize = rusts.iter().map(|r| r.size).sum();
+        let solana_total: DirSize = solanas.iter().map(|s| s.size).sum();
+        let simulator_total: DirSize = simulators.iter().map(|s| s.size).sum();
+        let cargo_total: DirSize = crates.iter().map(|c| c.size).sum();
+        let sccache_total: DirSize = sccache.iter().map(|e| e.size).sum();
 
         println!(
-            "🦀 Rust Toolchains: {} toolchains, {}",
+            "🦀 Rust Toolchains: {} toolchains, {}{}",
             rusts.len(),
-            Utils::format_size(rust_total).yellow()
+            Utils::format_dir_size(&rust_total).yellow(),
+            Self::share(&volumes, rust_root.as_deref(), &rust_total)
         );
 
         println!(
-            "⚡ Solana Versions: {} versions, {}",
+            "⚡ Solana Versions: {} versions, {}{}",
             solanas.len(),
-            Utils::format_size(solana_total).yellow()
+            Utils::format_dir_size(&solana_total).yellow(),
+            Self::share(&volumes, solana_root.as_deref(), &solana_total)
         );
 
         let unavailable_simulators = simulators.iter().filter(|s| !s.is_available).count();
+        let simulator_cache_total: DirSize = simulators
+            .iter()
+            .filter(|s| s.is_cache())
+            .map(|s| s.size)
+            .sum();
         println!(
-            "📱 iOS Simulators: {} simulators ({} unavailable), {}",
-            simulators.len(),
+            "📱 iOS Simulators: {} simulators ({} unavailable), {}{}",
+            simulators.iter().filter(|s| !s.is_cache()).count(),
             unavailable_simulators,
-            Utils::format_size(simulator_total).yellow()
+            Utils::format_dir_size(&simulator_total).yellow(),
+            Self::share(&volumes, Some(&simulator_root), &simulator_total)
+        );
+        println!(
+            "   ↳ CoreSimulator caches & logs: {}",
+            Utils::format_dir_size(&simulator_cache_total).yellow()
+        );
+
+        println!(
+            "📦 Cargo Binaries: {} crates, {}{}",
+            crates.len(),
+            Utils::format_dir_size(&cargo_total).yellow(),
+            Self::share(&volumes, cargo_root.as_deref(), &cargo_total)
+        );
+
+        println!(
+            "🗃️  sccache: {} entries, {}{}",
+            sccache.len(),
+            Utils::format_dir_size(&sccache_total).yellow(),
+            Self::share(&volumes, sccache_root.as_deref(), &sccache_total)
         );
 
+        let mut snapshot = Snapshot::now();
+        snapshot.record("Rust", rusts.iter().map(|r| (r.name.as_str(), r.size)));
+        snapshot.record("Solana", solanas.iter().map(|s| (s.name.as_str(), s.size)));
+        snapshot.record(
+            "Simulator",
+            simulators.iter().map(|s| (s.name.as_str(), s.size)),
+        );
+        snapshot.record("Cargo", crates.iter().map(|c| (c.name.as_str(), c.size)));
+        snapshot.record("sccache", [("cache", sccache_total)]);
+
+        let mut custom_total = DirSize::default();
+        for (target, items) in customs {
+            let items = Self::category(&target.name, items);
+            snapshot.record(
+                &target.name,
+                items.iter().map(|item| (item.name.as_str(), item.size)),
+            );
+            let size: DirSize = items.iter().map(|item| item.size).sum();
+            println!(
+                "📦 {}: {} items, {}{}",
+                target.name,
+                items.len(),
+                Utils::format_dir_size(&size).yellow(),
+                Self::share(&volumes, target.root().ok().as_deref(), &size)
+            );
+            custom_total += size;
+        }
+
+        let total = rust_total
+            + solana_total
+            + simulator_total
+            + cargo_total
+            + sccache_total
+            + custom_total;
         println!();
         println!(
-            "{} Total: {}",
+            "{} Total: {} logical, {} on disk, {} reclaimable",
             "💾".bold(),
-            Utils::format_size(rust_total + solana_total + simulator_total)
-                .bold()
-                .green()
+            Utils::format_size(total.logical).bold().green(),
+            Utils::format_size(total.allocated).bold(),
+            Utils::format_size(total.reclaimable).bold().green()
         );
+
+        if !volumes.is_empty() {
+            println!();
+            println!("{}", "💽 Disks:".bold().cyan());
+            for volume in &volumes {
+                println!(
+                    "   {:<20} {} used of {} ({:.0}%), {} free",
+                    volume.mount.display(),
+                    Utils::format_size(volume.used).yellow(),
+                    Utils::format_size(volume.total),
+                    volume.used_percent(),
+                    Utils::format_size(volume.free).bold().green()
+                );
+            }
+        }
+
+        if record || config.history.record {
+            History::append(env, &snapshot)?;
+            println!();
+            println!("{} Snapshot recorded.", "📝".bold());
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,565 @@
+use crate::commands::{
+    Audit, AuditEntry, Backend, Cache, CleanupError, DirSize, Env, Filter, LastUse, Removal,
+    Removals, Report, Result, Rust, Scan, ScanEvent, Utils,
+};
+use colored::Colorize;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+pub struct TargetDir;
+
+/// 一个 cargo target 目录
+#[derive(Debug)]
+pub struct TargetDirInfo {
+    pub path: PathBuf,
+    /// `.rustc_info.json` 记录的最近一次构建所用的 rustc，如 `rustc 1.75.0 (82e1608df 2023-12-21)`
+    pub rustc: Option<String>,
+    pub units: Vec<BuildUnit>,
+}
+
+impl TargetDirInfo {
+    /// 所属项目的名称，即 target 目录的上一级目录名
+    pub(crate) fn project(&self) -> String {
+        self.path
+            .parent()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+
+    /// 最近使用的产物，视为当前构建
+    pub(crate) fn current(&self) -> Option<&BuildUnit> {
+        self.units
+            .iter()
+            .max_by_key(|unit| unit.last_use.modified.max(unit.last_use.accessed))
+    }
+}
+
+/// 某个 profile 的构建产物；交叉编译的产物位于 `<triple>/<profile>`
+#[derive(Debug)]
+pub struct BuildUnit {
+    pub path: PathBuf,
+    pub triple: Option<String>,
+    pub profile: String,
+    pub size: DirSize,
+    pub last_use: LastUse,
+}
+
+impl BuildUnit {
+    /// 如 `release` 或 `x86_64-unknown-linux-musl/release`
+    pub fn label(&self) -> String {
+        match &self.triple {
+            Some(triple) => format!("{}/{}", triple, self.profile),
+            None => self.profile.clone(),
+        }
+    }
+}
+
+/// 构建产物可以删除的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stale {
+    /// 构建它的工具链已不再安装
+    Toolchain(String),
+    /// 超过 `--unused-for` 未被使用
+    Unused,
+}
+
+impl Stale {
+    fn describe(&self) -> String {
+        match self {
+            Stale::Toolchain(rustc) => format!("built by {}, not installed", rustc),
+            Stale::Unused => "unused".to_string(),
+        }
+    }
+}
+
+/// `.rustc_info.json` 中缓存的 rustc 查询结果
+#[derive(Debug, Default, Deserialize)]
+struct RustcInfo {
+    #[serde(default)]
+    outputs: BTreeMap<String, RustcOutput>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RustcOutput {
+    #[serde(default)]
+    stdout: String,
+}
+
+impl TargetDir {
+    /// cargo 会在每个 target 目录下写入 `.rustc_info.json`
+    fn is_target_dir(path: &Path) -> bool {
+        path.join(".rustc_info.json").is_file()
+    }
+
+    /// profile 目录中总有 `.fingerprint` 与 `deps`
+    fn is_profile_dir(path: &Path) -> bool {
+        path.join(".fingerprint").is_dir() || path.join("deps").is_dir()
+    }
+
+    /// 取出 `rustc -vV` 输出的第一行
+    fn read_rustc(path: &Path) -> Option<String> {
+        let text = fs::read_to_string(path.join(".rustc_info.json")).ok()?;
+        let info: RustcInfo = serde_json::from_str(&text).ok()?;
+        info.outputs.into_values().find_map(|output| {
+            output
+                .stdout
+                .lines()
+                .next()
+                .filter(|line| line.starts_with("rustc "))
+                .map(|line| line.trim().to_string())
+        })
+    }
+
+    /// 在根目录下查找 target 目录，不进入隐藏目录与已找到的 target 目录
+    fn find(roots: &[PathBuf], depth: usize) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for root in roots {
+            let mut walker = WalkDir::new(root).max_depth(depth).into_iter();
+            while let Some(entry) = walker.next() {
+                if Scan::is_cancelled() {
+                    break;
+                }
+                let Ok(entry) = entry else {
+                    continue;
+                };
+                if !entry.file_type().is_dir() {
+                    continue;
+                }
+                let hidden =
+                    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.');
+                if hidden {
+                    walker.skip_current_dir();
+                    continue;
+                }
+                if Self::is_target_dir(entry.path()) {
+                    found.push(entry.into_path());
+                    walker.skip_current_dir();
+                }
+            }
+        }
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// 按 profile 与目标三元组拆分一个 target 目录；`doc`、`tmp` 等其它目录不计入
+    fn unit_paths(target: &Path) -> Vec<(Option<String>, String, PathBuf)> {
+        let children = |dir: &Path| -> Vec<(String, PathBuf)> {
+            fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                        .map(|entry| {
+                            (
+                                entry.file_name().to_string_lossy().to_string(),
+                                entry.path(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let mut units = Vec::new();
+        for (name, path) in children(target) {
+            if Self::is_profile_dir(&path) {
+                units.push((None, name, path));
+                continue;
+            }
+            for (profile, path) in children(&path) {
+                if Self::is_profile_dir(&path) {
+                    units.push((Some(name.clone()), profile, path));
+                }
+            }
+        }
+        units.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        units
+    }
+
+    /// 要查找的根目录：命令行给出的路径，其次配置的 `roots`，最后是当前目录
+    fn roots(env: &Env, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        if !paths.is_empty() {
+            return Ok(paths.to_vec());
+        }
+        let roots = &env.settings().target_dir.roots;
+        if !roots.is_empty() {
+            return Ok(roots.clone());
+        }
+        let current = std::env::current_dir().map_err(|err| CleanupError::io(".", err))?;
+        Ok(vec![current])
+    }
+
+    /// 查找并拆分所有 target 目录
+    pub fn get_target_dirs(env: &Env, paths: &[PathBuf]) -> Result<Vec<TargetDirInfo>> {
+        let roots = Self::roots(env, paths)?;
+        let targets = Self::find(&roots, env.settings().target_dir.depth);
+        let units: Vec<(usize, Option<String>, String, PathBuf)> = targets
+            .iter()
+            .enumerate()
+            .flat_map(|(index, target)| {
+                Self::unit_paths(target)
+                    .into_iter()
+                    .map(move |(triple, profile, path)| (index, triple, profile, path))
+            })
+            .collect();
+        Scan::emit(ScanEvent::Discovered(units.len() as u64));
+
+        let measured: Vec<(usize, BuildUnit)> = units
+            .into_par_iter()
+            .filter(|_| !Scan::is_cancelled())
+            .map(|(index, triple, profile, path)| {
+                let (size, last_use) = Cache::measure_dir(&path);
+                Scan::emit(ScanEvent::Completed);
+                (
+                    index,
+                    BuildUnit {
+                        path,
+                        triple,
+                        profile,
+                        size,
+                        last_use,
+                    },
+                )
+            })
+            .collect();
+
+        let mut dirs: Vec<TargetDirInfo> = targets
+            .into_iter()
+            .map(|path| TargetDirInfo {
+                rustc: Self::read_rustc(&path),
+                path,
+                units: Vec::new(),
+            })
+            .collect();
+        for (index, unit) in measured {
+            dirs[index].units.push(unit);
+        }
+        Ok(dirs)
+    }
+
+    /// 可删除的产物及原因；每个 target 目录中最近使用的产物是当前构建，总是保留。
+    /// `installed` 为空表示无法确定已安装的工具链，此时只按 `--unused-for` 判断
+    pub(crate) fn stale_units<'a>(
+        dir: &'a TargetDirInfo,
+        installed: Option<&[String]>,
+        filter: &Filter,
+    ) -> Vec<(&'a BuildUnit, Stale)> {
+        let current = dir.current().map(|unit| unit.path.as_path());
+        let uninstalled = match (installed, &dir.rustc) {
+            (Some(installed), Some(rustc)) if !installed.contains(rustc) => Some(rustc.clone()),
+            _ => None,
+        };
+        dir.units
+            .iter()
+            .filter(|unit| Some(unit.path.as_path()) != current)
+            .filter(|unit| {
+                let label = unit.label();
+                let mut parts = vec![label.as_str(), unit.profile.as_str()];
+                parts.extend(unit.triple.as_deref());
+                filter.matches_parts(&parts)
+            })
+            .filter_map(|unit| {
+                if let Some(rustc) = &uninstalled {
+                    return Some((unit, Stale::Toolchain(rustc.clone())));
+                }
+                (filter.has_age_limit() && filter.is_unused(&unit.last_use))
+                    .then_some((unit, Stale::Unused))
+            })
+            .collect()
+    }
+
+    /// 已安装工具链的版本；没有 rustup 时跳过工具链检查
+    pub(crate) fn installed(env: &Env) -> Option<Vec<String>> {
+        match Rust::installed_versions(env) {
+            Ok(versions) => Some(versions),
+            Err(CleanupError::MissingTool(tool)) => {
+                eprintln!(
+                    "{} {} not installed, toolchain check skipped",
+                    "ℹ".blue(),
+                    tool
+                );
+                None
+            }
+            Err(err) => {
+                eprintln!("{} Rust: {}", "⚠".yellow(), err);
+                Report::record(err);
+                None
+            }
+        }
+    }
+
+    fn scan(env: &Env, paths: &[PathBuf]) -> Result<(Vec<TargetDirInfo>, Option<Vec<String>>)> {
+        Scan::begin("Target dirs");
+        let dirs = Self::get_target_dirs(env, paths);
+        Scan::finish();
+        let dirs = dirs?;
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+        let installed = if dirs.is_empty() {
+            None
+        } else {
+            Self::installed(env)
+        };
+        Ok((dirs, installed))
+    }
+
+    /// 列出 target 目录，按 profile 与三元组拆分，并标出可删除的产物
+    pub fn show_target_dirs(env: &Env, paths: &[PathBuf]) -> Result<()> {
+        println!("{}", "🎯 Cargo target directories:".bold().cyan());
+        println!();
+        let config = env.settings();
+        if !config.target_dir.enabled {
+            println!("Target directories are disabled in config.");
+            return Ok(());
+        }
+        let (dirs, installed) = Self::scan(env, paths)?;
+        if dirs.is_empty() {
+            println!("No target directories found.");
+            return Ok(());
+        }
+
+        for dir in &dirs {
+            let total: DirSize = dir.units.iter().map(|unit| unit.size).sum();
+            let rustc = match (&dir.rustc, &installed) {
+                (None, _) => "rustc unknown".dimmed(),
+                (Some(rustc), Some(installed)) if !installed.contains(rustc) => {
+                    format!("{} ✗ not installed", rustc).red()
+                }
+                (Some(rustc), Some(_)) => format!("{} ✓", rustc).green(),
+                (Some(rustc), None) => rustc.dimmed(),
+            };
+            println!(
+                "📁 {} {} {}",
+                dir.path.display().to_string().bold(),
+                Utils::format_dir_size(&total).yellow(),
+                rustc
+            );
+            let stale = Self::stale_units(dir, installed.as_deref(), &config.filter);
+            let current = dir.current().map(|unit| unit.path.as_path());
+            for unit in &dir.units {
+                let note = if Some(unit.path.as_path()) == current {
+                    "current build".green()
+                } else if let Some((_, reason)) = stale.iter().find(|(s, _)| s.path == unit.path) {
+                    reason.describe().red()
+                } else {
+                    "".normal()
+                };
+                println!(
+                    "   {:<45} {:>10} {:>8}  {}",
+                    unit.label(),
+                    Utils::format_dir_size(&unit.size).yellow(),
+                    Utils::format_age(&unit.last_use).dimmed(),
+                    note
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 删除一个 profile 的构建产物
+    pub fn remove_unit(env: &Env, item: &str, unit: &BuildUnit) -> Result<()> {
+        let entry = AuditEntry::new("target-dir", item, unit.size.reclaimable).path(&unit.path);
+        Audit::track(env, entry, || {
+            fs::remove_dir_all(&unit.path).map_err(|err| CleanupError::io(&unit.path, err))
+        })
+    }
+
+    /// 删除构建工具链已卸载或长期未使用的产物，保留每个 target 目录的当前构建
+    pub fn clean_target_dirs(env: &Env, paths: &[PathBuf]) -> Result<()> {
+        println!("{}", "🧹 Pruning cargo target directories:".bold().cyan());
+        println!();
+        let config = env.settings();
+        if !config.target_dir.enabled {
+            println!("Target directories are disabled in config.");
+            return Ok(());
+        }
+        let (dirs, installed) = Self::scan(env, paths)?;
+        let stale: Vec<(String, &BuildUnit, Stale)> = dirs
+            .iter()
+            .flat_map(|dir| {
+                let project = dir.project();
+                Self::stale_units(dir, installed.as_deref(), &config.filter)
+                    .into_iter()
+                    .map(move |(unit, reason)| {
+                        (format!("{}: {}", project, unit.label()), unit, reason)
+                    })
+            })
+            .collect();
+        if stale.is_empty() {
+            println!("No stale build artifacts.");
+            if !config.filter.has_age_limit() {
+                println!(
+                    "Use --unused-for to also prune profiles that have not been used recently."
+                );
+            }
+            return Ok(());
+        }
+
+        let items: Vec<String> = stale
+            .iter()
+            .map(|(item, unit, reason)| {
+                format!(
+                    "{} ({}) {}",
+                    item,
+                    Utils::format_dir_size(&unit.size),
+                    reason.describe()
+                )
+            })
+            .collect();
+        let selections = env
+            .multi_select("Select build artifacts to remove:", &items)
+            .map_err(CleanupError::Prompt)?;
+        if selections.is_empty() {
+            println!("No build artifacts selected.");
+            return Ok(());
+        }
+
+        let removals = selections
+            .iter()
+            .map(|&index| {
+                let (item, unit, _) = &stale[index];
+                Removal::new(
+                    "target-dir",
+                    item,
+                    unit.size.reclaimable,
+                    Some(&unit.path),
+                    Backend::Filesystem,
+                    move || Self::remove_unit(env, item, unit),
+                )
+            })
+            .collect();
+        Removals::execute(env, removals).finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        fs::FileTimes,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    fn build(target: &Path, unit: &str, days: u64) {
+        let dir = target.join(unit);
+        fs::create_dir_all(dir.join(".fingerprint")).expect("Failed to create fingerprint");
+        fs::create_dir_all(dir.join("deps")).expect("Failed to create deps");
+        fs::write(dir.join("deps/libapp.rlib"), [0u8; 4096]).expect("Failed to write rlib");
+        let then = SystemTime::now() - Duration::from_secs(days * 86_400);
+        let times = FileTimes::new().set_accessed(then).set_modified(then);
+        let mut paths: Vec<PathBuf> = WalkDir::new(&dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.into_path())
+            .collect();
+        paths.reverse();
+        for path in paths {
+            fs::File::open(&path)
+                .and_then(|file| file.set_times(times))
+                .expect("Failed to set file times");
+        }
+    }
+
+    fn rustc_info(target: &Path, version: &str) {
+        let info = serde_json::json!({
+            "rustc_fingerprint": 1,
+            "outputs": {
+                "1": {"success": true, "status": "", "code": 0, "stdout": "___\nlib___.rlib\n", "stderr": ""},
+                "2": {"success": true, "status": "", "code": 0, "stdout": format!("{}\nbinary: rustc\n", version), "stderr": ""},
+            }
+        });
+        fs::create_dir_all(target).expect("Failed to create target");
+        fs::write(target.join(".rustc_info.json"), info.to_string()).expect("Failed to write info");
+    }
+
+    #[test]
+    fn target_dirs_split_by_profile_and_prune_stale_toolchains() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("cleanup_target_dir_test_{}", nanos));
+        let current = "rustc 1.75.0 (82e1608df 2023-12-21)";
+        let app = root.join("app/target");
+        rustc_info(&app, current);
+        build(&app, "debug", 1);
+        build(&app, "release", 60);
+        build(&app, "x86_64-unknown-linux-musl/release", 90);
+        fs::create_dir_all(app.join("doc")).expect("Failed to create doc");
+        let old = root.join("nested/old/target");
+        rustc_info(&old, "rustc 1.60.0 (7737e0b5c 2022-04-04)");
+        build(&old, "debug", 100);
+        build(&old, "release", 200);
+
+        let env = Env::with_home(&root);
+        let dirs = TargetDir::get_target_dirs(&env, std::slice::from_ref(&root)).expect("dirs");
+        assert_eq!(dirs.len(), 2);
+        let app_dir = dirs.iter().find(|d| d.path == app).expect("app target");
+        let labels: Vec<String> = app_dir.units.iter().map(BuildUnit::label).collect();
+        assert_eq!(
+            labels,
+            ["debug", "release", "x86_64-unknown-linux-musl/release"]
+        );
+        assert_eq!(app_dir.rustc.as_deref(), Some(current));
+        let old_dir = dirs.iter().find(|d| d.path == old).expect("old target");
+
+        let installed = vec![current.to_string()];
+        let summary = |dir: &TargetDirInfo, filter: &Filter| -> Vec<(String, Stale)> {
+            TargetDir::stale_units(dir, Some(&installed), filter)
+                .into_iter()
+                .map(|(unit, reason)| (unit.label(), reason))
+                .collect()
+        };
+        let no_age = Filter::default();
+        assert!(summary(app_dir, &no_age).is_empty());
+        assert_eq!(
+            summary(old_dir, &no_age),
+            [(
+                "release".to_string(),
+                Stale::Toolchain("rustc 1.60.0 (7737e0b5c 2022-04-04)".to_string())
+            )]
+        );
+
+        let month = Filter::default().unused_for(Some(Duration::from_secs(30 * 86_400)));
+        assert_eq!(
+            summary(app_dir, &month),
+            [
+                ("release".to_string(), Stale::Unused),
+                (
+                    "x86_64-unknown-linux-musl/release".to_string(),
+                    Stale::Unused
+                ),
+            ]
+        );
+
+        // 排除的 profile 在交叉编译目录下同样被排除，三元组也可单独包含或排除
+        let filtered = |include: &[&str], exclude: &[&str]| {
+            let strings = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+            let filter = Filter::new(&strings(include), &strings(exclude))
+                .expect("valid globs")
+                .unused_for(Some(Duration::from_secs(30 * 86_400)));
+            summary(app_dir, &filter)
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>()
+        };
+        assert!(filtered(&[], &["release"]).is_empty());
+        assert_eq!(
+            filtered(&["release"], &["x86_64-*"]),
+            ["release".to_string()]
+        );
+        assert_eq!(
+            filtered(&["x86_64-*"], &[]),
+            ["x86_64-unknown-linux-musl/release".to_string()]
+        );
+        fs::remove_dir_all(&root).expect("Failed to remove temp directory");
+    }
+}
@@ -0,0 +1,679 @@
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
+};
+use std::collections::HashSet;
+
+use crate::commands::{
+    CleanupError, DirSize, Env, LastUse, Reclaim, Removal, Removals, Result, Scan, Utils,
+    reclaim::Action,
+};
+
+/// 列表的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Size,
+    Age,
+    Name,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Size => SortKey::Age,
+            SortKey::Age => SortKey::Name,
+            SortKey::Name => SortKey::Size,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Size => "size",
+            SortKey::Age => "age",
+            SortKey::Name => "name",
+        }
+    }
+}
+
+/// 浏览器中的一个条目
+struct Item {
+    name: String,
+    size: DirSize,
+    last_use: LastUse,
+    /// 不可删除的原因：使用中、受保护或按保留策略保留
+    lock: Option<&'static str>,
+    action: Action,
+    marked: bool,
+}
+
+/// 一个类别及其条目
+struct Group {
+    name: String,
+    expanded: bool,
+    items: Vec<Item>,
+}
+
+impl Group {
+    fn size(&self) -> DirSize {
+        self.items.iter().map(|item| item.size).sum()
+    }
+}
+
+/// 列表中的一行：类别或类别中的条目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Group(usize),
+    Item(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Search,
+    Confirm,
+}
+
+/// 按键处理后需要事件循环执行的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    None,
+    Quit,
+    Delete,
+}
+
+/// 与终端无关的浏览状态
+struct Browser {
+    groups: Vec<Group>,
+    sort: SortKey,
+    search: String,
+    cursor: usize,
+    mode: Mode,
+    message: String,
+}
+
+impl Browser {
+    fn new(groups: Vec<Group>) -> Self {
+        let mut browser = Browser {
+            groups,
+            sort: SortKey::Size,
+            search: String::new(),
+            cursor: 0,
+            mode: Mode::Browse,
+            message: String::new(),
+        };
+        browser.sort_items();
+        browser
+    }
+
+    fn matches_search(&self, item: &Item) -> bool {
+        self.search.is_empty()
+            || item
+                .name
+                .to_lowercase()
+                .contains(&self.search.to_lowercase())
+    }
+
+    /// 当前可见的行；搜索时只显示有匹配条目的类别，并自动展开
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (g, group) in self.groups.iter().enumerate() {
+            let items: Vec<usize> = (0..group.items.len())
+                .filter(|&i| self.matches_search(&group.items[i]))
+                .collect();
+            if !self.search.is_empty() && items.is_empty() {
+                continue;
+            }
+            rows.push(Row::Group(g));
+            if group.expanded || !self.search.is_empty() {
+                rows.extend(items.into_iter().map(|i| Row::Item(g, i)));
+            }
+        }
+        rows
+    }
+
+    fn current(&self) -> Option<Row> {
+        self.rows().get(self.cursor).copied()
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let len = self.rows().len();
+        self.cursor = self
+            .cursor
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    fn clamp_cursor(&mut self) {
+        self.move_cursor(0);
+    }
+
+    fn sort_items(&mut self) {
+        for group in &mut self.groups {
+            match self.sort {
+                SortKey::Size => group
+                    .items
+                    .sort_by_key(|item| std::cmp::Reverse(item.size.reclaimable)),
+                // 最久未使用的在前，时间未知的排在最后
+                SortKey::Age => group.items.sort_by_key(|item| {
+                    let latest = item.last_use.modified.max(item.last_use.accessed);
+                    if latest == 0 { u64::MAX } else { latest }
+                }),
+                SortKey::Name => group.items.sort_by(|a, b| a.name.cmp(&b.name)),
+            }
+        }
+    }
+
+    fn set_expanded(&mut self, expanded: bool) {
+        match self.current() {
+            Some(Row::Group(g)) => self.groups[g].expanded = expanded,
+            Some(Row::Item(g, _)) if !expanded => {
+                self.groups[g].expanded = false;
+                let rows = self.rows();
+                self.cursor = rows
+                    .iter()
+                    .position(|row| *row == Row::Group(g))
+                    .unwrap_or(0);
+            }
+            _ => {}
+        }
+        self.clamp_cursor();
+    }
+
+    /// 标记或取消标记当前条目；在类别上时切换该类别中所有可见且可删除的条目
+    fn toggle_mark(&mut self) {
+        match self.current() {
+            Some(Row::Item(g, i)) => {
+                let item = &mut self.groups[g].items[i];
+                match item.lock {
+                    Some(reason) => self.message = format!("{} is {}", item.name, reason),
+                    None => item.marked = !item.marked,
+                }
+            }
+            Some(Row::Group(g)) => {
+                let visible: Vec<usize> = (0..self.groups[g].items.len())
+                    .filter(|&i| {
+                        let item = &self.groups[g].items[i];
+                        item.lock.is_none() && self.matches_search(item)
+                    })
+                    .collect();
+                let items = &mut self.groups[g].items;
+                let mark = visible.iter().any(|&i| !items[i].marked);
+                for i in visible {
+                    items[i].marked = mark;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// 已标记条目的数量与可回收大小
+    fn marked_total(&self) -> (usize, u64) {
+        self.groups
+            .iter()
+            .flat_map(|group| group.items.iter().filter(|item| item.marked))
+            .fold((0, 0), |(count, size), item| {
+                (count + 1, size + item.size.reclaimable)
+            })
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Command {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Command::Quit;
+        }
+        match self.mode {
+            Mode::Search => match key.code {
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.mode = Mode::Browse;
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Char(c) => self.search.push(c),
+                _ => {}
+            },
+            Mode::Confirm => {
+                self.mode = Mode::Browse;
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    return Command::Delete;
+                }
+                self.message = "Nothing removed.".to_string();
+            }
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Command::Quit,
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+                KeyCode::PageUp => self.move_cursor(-10),
+                KeyCode::PageDown => self.move_cursor(10),
+                KeyCode::Home | KeyCode::Char('g') => self.cursor = 0,
+                KeyCode::End | KeyCode::Char('G') => self.move_cursor(isize::MAX),
+                KeyCode::Right | KeyCode::Char('l') => self.set_expanded(true),
+                KeyCode::Left | KeyCode::Char('h') => self.set_expanded(false),
+                KeyCode::Enter => {
+                    if let Some(Row::Group(g)) = self.current() {
+                        self.groups[g].expanded = !self.groups[g].expanded;
+                    }
+                }
+                KeyCode::Char(' ') => self.toggle_mark(),
+                KeyCode::Char('s') => {
+                    self.sort = self.sort.next();
+                    self.sort_items();
+                }
+                KeyCode::Char('/') => {
+                    self.mode = Mode::Search;
+                    self.cursor = 0;
+                }
+                KeyCode::Char('d') => {
+                    if self.marked_total().0 == 0 {
+                        self.message = "Mark items with space first.".to_string();
+                    } else {
+                        self.mode = Mode::Confirm;
+                    }
+                }
+                _ => {}
+            },
+        }
+        self.clamp_cursor();
+        Command::None
+    }
+
+    /// 经由 `Removals::execute` 删除所有已标记的条目（未加 `--force` 时跳过使用中的条目），
+    /// 成功删除的从列表中移除
+    fn delete_marked(&mut self, env: &Env) -> Removals {
+        let removals = self
+            .groups
+            .iter()
+            .flat_map(|group| {
+                group.items.iter().filter(|item| item.marked).map(|item| {
+                    Removal::new(
+                        &group.name,
+                        &item.name,
+                        item.size.reclaimable,
+                        item.action.path(),
+                        item.action.backend(),
+                        || item.action.remove(env, &group.name),
+                    )
+                })
+            })
+            .collect();
+        let removals = Removals::execute(env, removals);
+
+        let removed: HashSet<(&str, &str)> = removals
+            .items
+            .iter()
+            .filter(|result| result.removed)
+            .map(|result| (result.target.as_str(), result.item.as_str()))
+            .collect();
+        for group in &mut self.groups {
+            group
+                .items
+                .retain(|item| !removed.contains(&(group.name.as_str(), item.name.as_str())));
+            for item in &mut group.items {
+                item.marked = false;
+            }
+        }
+        self.message = format!(
+            "Removed {} items, freed {}",
+            removals.removed,
+            Utils::format_size(removals.freed)
+        );
+        if let Some(failure) = removals.items.iter().find(|result| !result.removed) {
+            self.message += &format!(
+                " ({} failed: {})",
+                removals.failed,
+                failure.error.as_deref().unwrap_or_default()
+            );
+        }
+        self.clamp_cursor();
+        removals
+    }
+}
+
+/// 基于现有扫描函数的全屏浏览器
+pub struct Tui;
+
+impl Tui {
+    /// 按目标分组所有已启用目标中的条目
+    fn load(env: &Env) -> Vec<Group> {
+        let mut groups: Vec<Group> = Vec::new();
+        for candidate in Reclaim::collect(env) {
+            let item = Item {
+                name: candidate.name,
+                size: candidate.size,
+                last_use: candidate.last_use,
+                lock: candidate.lock,
+                action: candidate.action,
+                marked: false,
+            };
+            match groups.last_mut() {
+                Some(group) if group.name == candidate.category => group.items.push(item),
+                _ => groups.push(Group {
+                    name: candidate.category,
+                    expanded: false,
+                    items: vec![item],
+                }),
+            }
+        }
+        groups
+    }
+
+    fn render_row(browser: &Browser, row: Row) -> ListItem<'static> {
+        match row {
+            Row::Group(g) => {
+                let group = &browser.groups[g];
+                let arrow = if group.expanded || !browser.search.is_empty() {
+                    "▾"
+                } else {
+                    "▸"
+                };
+                ListItem::new(Line::from(vec![
+                    Span::from(format!("{} {:<48}", arrow, group.name)).bold(),
+                    Span::from(format!(
+                        "{:>12}  {} items",
+                        Utils::format_size(group.size().reclaimable),
+                        group.items.len()
+                    ))
+                    .yellow(),
+                ]))
+            }
+            Row::Item(g, i) => {
+                let item = &browser.groups[g].items[i];
+                let mark = match (item.lock, item.marked) {
+                    (Some(_), _) => "🔒 ",
+                    (None, true) => "[x]",
+                    (None, false) => "[ ]",
+                };
+                let mut spans = vec![
+                    Span::from(format!("   {} {:<44}", mark, item.name)),
+                    Span::from(format!("{:>12}", Utils::format_size(item.size.reclaimable)))
+                        .yellow(),
+                    Span::from(format!("  {:>8}", Utils::format_age(&item.last_use))).dim(),
+                ];
+                if let Some(reason) = item.lock {
+                    spans.push(Span::from(format!("  {}", reason)).blue());
+                }
+                let line = Line::from(spans);
+                ListItem::new(if item.marked { line.red() } else { line })
+            }
+        }
+    }
+
+    fn draw(frame: &mut Frame, browser: &Browser) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+
+        let total: u64 = browser
+            .groups
+            .iter()
+            .map(|group| group.size().reclaimable)
+            .sum();
+        let (count, selected) = browser.marked_total();
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::from(" 📊 Storage ").bold().cyan(),
+                Span::from(format!(
+                    "total {} · sorted by {} · selected {} items, {}",
+                    Utils::format_size(total),
+                    browser.sort.label(),
+                    count,
+                    Utils::format_size(selected)
+                )),
+            ])),
+            header,
+        );
+
+        let rows = browser.rows();
+        let items: Vec<ListItem> = rows
+            .iter()
+            .map(|&row| Self::render_row(browser, row))
+            .collect();
+        let title = if browser.search.is_empty() {
+            String::new()
+        } else {
+            format!(" search: {} ", browser.search)
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(browser.cursor));
+        frame.render_stateful_widget(list, body, &mut state);
+
+        let help = match browser.mode {
+            Mode::Search => "type to search · enter keep filter · esc clear",
+            _ => {
+                "↑↓ move · ←→ collapse/expand · space mark · s sort · / search · d delete · q quit"
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(browser.message.clone()).fg(Color::Green),
+                Line::from(help).dim(),
+            ]),
+            footer,
+        );
+
+        if browser.mode == Mode::Confirm {
+            let area = frame
+                .area()
+                .centered(Constraint::Percentage(60), Constraint::Length(5));
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(vec![
+                    Line::from(format!(
+                        "Remove {} items to free {}?",
+                        count,
+                        Utils::format_size(selected)
+                    )),
+                    Line::from(""),
+                    Line::from("y confirm · any other key cancel").dim(),
+                ])
+                .centered()
+                .block(Block::bordered().title(" Delete ").red()),
+                area,
+            );
+        }
+    }
+
+    fn event_loop(
+        terminal: &mut DefaultTerminal,
+        env: &Env,
+        browser: &mut Browser,
+    ) -> std::io::Result<Removals> {
+        let mut removals = Removals::new();
+        loop {
+            terminal.draw(|frame| Self::draw(frame, browser))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match browser.handle_key(key) {
+                Command::None => {}
+                Command::Quit => return Ok(removals),
+                Command::Delete => {
+                    // 删除过程会逐条打印进度，暂时回到主屏幕，避免破坏全屏界面
+                    terminal::disable_raw_mode()?;
+                    execute!(std::io::stdout(), LeaveAlternateScreen)?;
+                    removals.merge(browser.delete_marked(env));
+                    execute!(std::io::stdout(), EnterAlternateScreen)?;
+                    terminal::enable_raw_mode()?;
+                    terminal.clear()?;
+                }
+            }
+        }
+    }
+
+    /// 扫描后进入全屏浏览；退出后汇总本次删除的所有条目
+    pub fn run(env: &Env) -> Result<()> {
+        env.home()?;
+        Scan::begin("Scanning");
+        let groups = Self::load(env);
+        Scan::finish();
+        if Scan::is_cancelled() {
+            return Err(CleanupError::Cancelled);
+        }
+
+        let mut browser = Browser::new(groups);
+        let mut terminal = ratatui::try_init().map_err(CleanupError::Prompt)?;
+        let result = Self::event_loop(&mut terminal, env, &mut browser);
+        ratatui::restore();
+
+        result.map_err(CleanupError::Prompt)?.finish(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::custom::CustomInfo;
+    use std::path::PathBuf;
+
+    fn item(name: &str, size: u64, modified: u64, lock: Option<&'static str>) -> Item {
+        let info = CustomInfo {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            size: DirSize::from_logical(size),
+            is_active: lock.is_some(),
+            version: None,
+            last_use: LastUse {
+                modified,
+                accessed: 0,
+            },
+        };
+        Item {
+            name: name.to_string(),
+            size: info.size,
+            last_use: info.last_use,
+            lock,
+            action: Action::Custom(info),
+            marked: false,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn browser_sorts_searches_and_totals_marked_items() {
+        let mut browser = Browser::new(vec![
+            Group {
+                name: "models".to_string(),
+                expanded: false,
+                items: vec![
+                    item("small", 10, 300, None),
+                    item("large", 30, 100, None),
+                    item("current", 50, 200, Some("active")),
+                ],
+            },
+            Group {
+                name: "Rust".to_string(),
+                expanded: false,
+                items: vec![item("stable", 20, 50, None)],
+            },
+        ]);
+        assert_eq!(browser.rows(), [Row::Group(0), Row::Group(1)]);
+
+        // 展开后按大小排序，标记整个类别时跳过使用中的条目
+        browser.handle_key(key(KeyCode::Right));
+        let names: Vec<&str> = browser.groups[0]
+            .items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(names, ["current", "large", "small"]);
+        browser.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(browser.marked_total(), (2, 40));
+
+        browser.handle_key(key(KeyCode::Char('s')));
+        assert_eq!(browser.sort, SortKey::Age);
+        assert_eq!(browser.groups[0].items[0].name, "large");
+
+        // 搜索只显示匹配的条目，取消标记其中一个
+        for c in "/sma".chars() {
+            browser.handle_key(key(KeyCode::Char(c)));
+        }
+        browser.handle_key(key(KeyCode::Enter));
+        assert_eq!(browser.rows(), [Row::Group(0), Row::Item(0, 2)]);
+        browser.handle_key(key(KeyCode::Down));
+        browser.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(browser.marked_total(), (1, 30));
+
+        browser.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(browser.mode, Mode::Confirm);
+        assert_eq!(browser.handle_key(key(KeyCode::Char('y'))), Command::Delete);
+        assert_eq!(browser.handle_key(key(KeyCode::Char('q'))), Command::Quit);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn delete_marked_skips_items_used_by_running_processes() {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("cleanup_tui_in_use_test_{}", nanos));
+        let entry = |name: &str| {
+            let path = home.join(name);
+            std::fs::create_dir_all(&path).expect("Failed to create item");
+            let mut entry = item(path.to_str().expect("utf-8 path"), 10, 100, None);
+            entry.marked = true;
+            entry
+        };
+        let busy = entry("busy");
+        let idle = entry("idle");
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .current_dir(home.join("busy"))
+            .spawn()
+            .expect("Failed to spawn sleep");
+
+        let mut browser = Browser::new(vec![Group {
+            name: "models".to_string(),
+            expanded: true,
+            items: vec![busy, idle],
+        }]);
+        let env = Env::with_home(&home);
+        let removals = browser.delete_marked(&env);
+        child.kill().expect("Failed to kill sleep");
+        child.wait().expect("Failed to wait for sleep");
+
+        assert_eq!((removals.removed, removals.failed), (1, 1));
+        match removals.finish(&env) {
+            Err(CleanupError::PartialDeletion {
+                attempted,
+                failures,
+            }) => {
+                assert_eq!(attempted, 2);
+                assert!(failures[0].1.contains("in use"), "{}", failures[0].1);
+            }
+            other => panic!("expected a partial deletion, got {:?}", other),
+        }
+        assert!(home.join("busy").exists());
+        assert!(!home.join("idle").exists());
+        let names: Vec<&str> = browser.groups[0]
+            .items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(names, [home.join("busy").to_str().unwrap_or_default()]);
+        assert!(!browser.groups[0].items[0].marked);
+        std::fs::remove_dir_all(&home).expect("Failed to remove temp directory");
+    }
+}
@@ -1,25 +1,388 @@
-use std::{env, fs, path::Path};
+use std::{
+    collections::HashMap,
+    env, fs,
+    iter::Sum,
+    ops::{Add, AddAssign},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use walkdir::WalkDir;
 
+use crate::commands::{CleanupError, Scan, ScanEvent};
+
 pub struct Utils;
 
+/// 目录占用：逻辑大小、磁盘分配大小以及删除后可回收的大小
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirSize {
+    /// 文件的表观大小之和（硬链接只计一次）
+    pub logical: u64,
+    /// 按 `st_blocks` 统计的磁盘分配大小（硬链接只计一次）
+    pub allocated: u64,
+    /// 删除该目录后真正能释放的字节数：所有硬链接都位于目录内的文件的分配大小
+    pub reclaimable: u64,
+}
+
+impl DirSize {
+    /// 仅知道逻辑大小时（例如来自外部工具的统计）使用
+    pub fn from_logical(size: u64) -> Self {
+        DirSize {
+            logical: size,
+            allocated: size,
+            reclaimable: size,
+        }
+    }
+}
+
+impl Add for DirSize {
+    type Output = DirSize;
+
+    fn add(self, other: DirSize) -> DirSize {
+        DirSize {
+            logical: self.logical + other.logical,
+            allocated: self.allocated + other.allocated,
+            reclaimable: self.reclaimable + other.reclaimable,
+        }
+    }
+}
+
+impl AddAssign for DirSize {
+    fn add_assign(&mut self, other: DirSize) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for DirSize {
+    fn sum<I: Iterator<Item = DirSize>>(iter: I) -> DirSize {
+        iter.fold(DirSize::default(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a DirSize> for DirSize {
+    fn sum<I: Iterator<Item = &'a DirSize>>(iter: I) -> DirSize {
+        iter.copied().sum()
+    }
+}
+
+/// 最近一次修改与访问的时间（Unix 秒），0 表示未知
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastUse {
+    pub modified: u64,
+    pub accessed: u64,
+}
+
+impl LastUse {
+    /// 单个文件或目录的修改与访问时间；遍历目录本身会更新目录的访问时间，
+    /// 因此目录只取修改时间
+    pub fn of(metadata: &fs::Metadata) -> LastUse {
+        let secs = |time: std::io::Result<SystemTime>| {
+            time.ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs())
+        };
+        LastUse {
+            modified: secs(metadata.modified()),
+            accessed: if metadata.is_dir() {
+                0
+            } else {
+                secs(metadata.accessed())
+            },
+        }
+    }
+
+    /// 取两者中较新的时间
+    pub fn merge(self, other: LastUse) -> LastUse {
+        LastUse {
+            modified: self.modified.max(other.modified),
+            accessed: self.accessed.max(other.accessed),
+        }
+    }
+
+    /// 距今未被修改或访问的时长；时间未知时返回 `None`
+    pub fn idle_for(&self, now: SystemTime) -> Option<Duration> {
+        let latest = self.modified.max(self.accessed);
+        if latest == 0 {
+            return None;
+        }
+        let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(Duration::from_secs(now.saturating_sub(latest)))
+    }
+}
+
+/// 一次目录遍历的结果：占用大小、最近使用时间以及树中每个目录的修改时间
+#[derive(Debug, Default)]
+pub struct DirScan {
+    pub size: DirSize,
+    pub last_use: LastUse,
+    pub dirs: Vec<(PathBuf, u64)>,
+}
+
 impl Utils {
-    /// 计算文件大小的辅助函数
-    pub fn calculate_dir_size(dir: &Path) -> u64 {
-        if dir.exists() {
-            WalkDir::new(dir)
-                .into_iter()
-                .filter_map(|entry| entry.ok())
-                .filter_map(|entry| entry.metadata().ok())
-                .filter(|metadata| metadata.is_file())
-                .map(|metadata| metadata.len())
-                .sum()
+    /// 获取路径的修改时间（纳秒时间戳）
+    pub fn modified_nanos(metadata: &fs::Metadata) -> Option<u64> {
+        metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_nanos() as u64)
+    }
+
+    /// 遍历目录统计逻辑大小、分配大小与可回收大小，按 (dev, inode) 去重硬链接，
+    /// 同时记录每个子目录的修改时间供扫描缓存校验
+    pub fn scan_dir(dir: &Path) -> DirScan {
+        if !dir.exists() {
+            return DirScan::default();
+        }
+
+        // (dev, inode) -> (目录内出现的次数, 链接总数, 逻辑大小, 分配大小)
+        let mut inodes: HashMap<(u64, u64), (u64, u64, u64, u64)> = HashMap::new();
+        let mut size = DirSize::default();
+        let mut last_use = LastUse::default();
+        let mut dirs = Vec::new();
+
+        for (path, metadata) in WalkDir::new(dir)
+            .into_iter()
+            .take_while(|_| !Scan::is_cancelled())
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.metadata().ok().map(|m| (entry.into_path(), m)))
+        {
+            last_use = last_use.merge(LastUse::of(&metadata));
+            if metadata.is_dir() {
+                Scan::emit(ScanEvent::Entered(&path));
+                dirs.push((path, Self::modified_nanos(&metadata).unwrap_or_default()));
+                continue;
+            }
+            if !metadata.is_file() {
+                continue;
+            }
+            Scan::emit(ScanEvent::Bytes(metadata.len()));
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                if metadata.nlink() > 1 {
+                    let entry = inodes.entry((metadata.dev(), metadata.ino())).or_insert((
+                        0,
+                        metadata.nlink(),
+                        metadata.len(),
+                        metadata.blocks() * 512,
+                    ));
+                    entry.0 += 1;
+                    continue;
+                }
+                let allocated = metadata.blocks() * 512;
+                size += DirSize {
+                    logical: metadata.len(),
+                    allocated,
+                    reclaimable: allocated,
+                };
+            }
+
+            #[cfg(not(unix))]
+            {
+                size += DirSize::from_logical(metadata.len());
+            }
+        }
+
+        for (seen, nlink, logical, allocated) in inodes.into_values() {
+            size.logical += logical;
+            size.allocated += allocated;
+            if seen >= nlink {
+                size.reclaimable += allocated;
+            }
+        }
+
+        DirScan {
+            size,
+            last_use,
+            dirs,
+        }
+    }
+
+    /// 向 JSON Lines 文件追加一条记录，目录不存在时创建；
+    /// 整行一次写入，并行删除时的多条记录不会互相穿插
+    pub fn append_json_line<T: Serialize>(path: &Path, record: &T) -> crate::commands::Result<()> {
+        use std::io::Write;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| CleanupError::io(dir, err))?;
+        }
+        let line = serde_json::to_string(record).map_err(|err| CleanupError::Parse {
+            what: path.display().to_string(),
+            message: err.to_string(),
+        })? + "\n";
+        static APPEND: Mutex<()> = Mutex::new(());
+        let _guard = APPEND.lock().unwrap_or_else(|err| err.into_inner());
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|err| CleanupError::io(path, err))
+    }
+
+    /// 按顺序读取 JSON Lines 文件中的所有记录；文件不存在时为空
+    pub fn read_json_lines<T: DeserializeOwned>(path: &Path) -> crate::commands::Result<Vec<T>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(CleanupError::io(path, err)),
+        };
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|err| CleanupError::Parse {
+                    what: format!("{} line {}", path.display(), index + 1),
+                    message: err.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// 以 "逻辑大小 (可回收大小)" 的形式展示目录占用
+    pub fn format_dir_size(size: &DirSize) -> String {
+        if size.reclaimable == size.logical {
+            Self::format_size(size.logical)
         } else {
-            0
+            format!(
+                "{} ({} reclaimable)",
+                Self::format_size(size.logical),
+                Self::format_size(size.reclaimable)
+            )
+        }
+    }
+
+    /// 以 "3d ago" 的形式展示最近使用时间，未知时显示 "-"
+    pub fn format_age(last_use: &LastUse) -> String {
+        let Some(idle) = last_use.idle_for(SystemTime::now()) else {
+            return "-".to_string();
+        };
+        let secs = idle.as_secs();
+        match secs {
+            0..3_600 => format!("{}m ago", secs / 60),
+            3_600..86_400 => format!("{}h ago", secs / 3_600),
+            86_400..5_184_000 => format!("{}d ago", secs / 86_400),
+            5_184_000..63_072_000 => format!("{}mo ago", secs / 2_592_000),
+            _ => format!("{}y ago", secs / 31_536_000),
         }
     }
 
+    /// 解析 `30d`、`12h`、`2w` 这样的时长，单位为 s/m/h/d/w
+    pub fn parse_duration(text: &str) -> Result<Duration, String> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| format!("missing unit in '{}' (use s, m, h, d or w)", text))?;
+        let (value, unit) = text.split_at(split);
+        let value: u64 = value
+            .parse()
+            .map_err(|_| format!("invalid number in '{}'", text))?;
+        let unit = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3_600,
+            "d" => 86_400,
+            "w" => 604_800,
+            _ => return Err(format!("unknown unit '{}' (use s, m, h, d or w)", unit)),
+        };
+        value
+            .checked_mul(unit)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("duration '{}' is too large", text))
+    }
+
+    /// 公历日期与 1970-01-01 之间相差的天数
+    fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// 1970-01-01 之后第 `days` 天的公历日期
+    fn civil_from_days(days: i64) -> (i64, i64, i64) {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+
+    /// 以 UTC 的 `2024-05-01 13:45` 形式展示 Unix 时间戳
+    pub fn format_date(secs: u64) -> String {
+        let secs = secs as i64;
+        let (year, month, day) = Self::civil_from_days(secs.div_euclid(86_400));
+        let minutes = secs.rem_euclid(86_400) / 60;
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            year,
+            month,
+            day,
+            minutes / 60,
+            minutes % 60
+        )
+    }
+
+    /// 解析 `2024-05-01`（UTC 零点）或 `7d` 这样相对现在的时长，返回 Unix 时间戳
+    pub fn parse_date(text: &str) -> Result<u64, String> {
+        let text = text.trim();
+        let parts: Vec<&str> = text.split('-').collect();
+        if let [year, month, day] = parts[..] {
+            let invalid = || format!("invalid date '{}' (use YYYY-MM-DD)", text);
+            let year: i64 = year.parse().map_err(|_| invalid())?;
+            let month: i64 = month.parse().map_err(|_| invalid())?;
+            let day: i64 = day.parse().map_err(|_| invalid())?;
+            if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+                return Err(invalid());
+            }
+            return Ok(Self::days_from_civil(year, month, day) as u64 * 86_400);
+        }
+        let ago = Self::parse_duration(text)
+            .map_err(|_| format!("invalid date '{}' (use YYYY-MM-DD or e.g. 7d)", text))?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(now.saturating_sub(ago).as_secs())
+    }
+
+    /// 解析 `20GB`、`512M`、`1.5TB` 这样的大小，单位按 1024 进位
+    pub fn parse_size(text: &str) -> Result<u64, String> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (value, unit) = text.split_at(split);
+        let value: f64 = value
+            .parse()
+            .map_err(|_| format!("invalid size '{}'", text))?;
+        let shift = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 0,
+            "K" | "KB" => 10,
+            "M" | "MB" => 20,
+            "G" | "GB" => 30,
+            "T" | "TB" => 40,
+            _ => return Err(format!("unknown unit '{}' (use B, KB, MB, GB or TB)", unit)),
+        };
+        Ok((value * (1u64 << shift) as f64) as u64)
+    }
+
     /// kb, mb, gb 格式化大小的辅助函数
     pub fn format_size(size: u64) -> String {
         const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -130,12 +493,98 @@ mod tests {
         file.write_all(&vec![0u8; 1024])
             .expect("Failed to write nested file");
 
-        let measured = Utils::calculate_dir_size(&temp_dir);
-        assert_eq!(measured, 2048 + 1024);
+        let measured = Utils::scan_dir(&temp_dir).size;
+        assert_eq!(measured.logical, 2048 + 1024);
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to remove temp directory");
+    }
+
+    /// 硬链接只计一次，且链接到目录外的文件不计入可回收大小
+    #[cfg(unix)]
+    #[test]
+    fn scan_dir_deduplicates_hard_links() {
+        let temp_dir = create_temp_dir();
+        let inside = temp_dir.join("inside");
+        fs::create_dir(&inside).expect("Failed to create inside directory");
+
+        let shared = inside.join("shared.bin");
+        File::create(&shared)
+            .and_then(|mut file| file.write_all(&vec![1u8; 8192]))
+            .expect("Failed to write shared file");
+        fs::hard_link(&shared, inside.join("shared-link.bin")).expect("Failed to hard link");
+
+        let escaping = inside.join("escaping.bin");
+        File::create(&escaping)
+            .and_then(|mut file| file.write_all(&vec![1u8; 4096]))
+            .expect("Failed to write escaping file");
+        fs::hard_link(&escaping, temp_dir.join("outside.bin")).expect("Failed to hard link");
+
+        let measured = Utils::scan_dir(&inside).size;
+        assert_eq!(measured.logical, 8192 + 4096);
+        assert!(measured.allocated >= 8192 + 4096);
+        assert!(measured.reclaimable >= 8192);
+        assert!(measured.reclaimable < measured.allocated);
 
         fs::remove_dir_all(&temp_dir).expect("Failed to remove temp directory");
     }
 
+    #[test]
+    fn parse_size_uses_binary_units() {
+        assert_eq!(Utils::parse_size("20GB"), Ok(20 << 30));
+        assert_eq!(Utils::parse_size("512m"), Ok(512 << 20));
+        assert_eq!(Utils::parse_size("1.5 TB"), Ok(3 << 39));
+        assert_eq!(Utils::parse_size("100"), Ok(100));
+        assert!(Utils::parse_size("GB").is_err());
+        assert!(Utils::parse_size("3 parsecs").is_err());
+    }
+
+    #[test]
+    fn parse_duration_and_format_age() {
+        assert_eq!(
+            Utils::parse_duration("30d"),
+            Ok(Duration::from_secs(30 * 86_400))
+        );
+        assert_eq!(
+            Utils::parse_duration("2w"),
+            Ok(Duration::from_secs(1_209_600))
+        );
+        assert!(Utils::parse_duration("30").is_err());
+        assert!(Utils::parse_duration("d").is_err());
+        assert!(Utils::parse_duration("3y").is_err());
+        assert!(Utils::parse_duration("99999999999999w").is_err());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let last_use = LastUse {
+            modified: now - 10 * 86_400,
+            accessed: now - 3 * 86_400,
+        };
+        assert_eq!(Utils::format_age(&last_use), "3d ago");
+        assert_eq!(Utils::format_age(&LastUse::default()), "-");
+    }
+
+    #[test]
+    fn parse_and_format_dates_in_utc() {
+        assert_eq!(Utils::parse_date("1970-01-01"), Ok(0));
+        assert_eq!(Utils::parse_date("2024-03-01"), Ok(1_709_251_200));
+        assert_eq!(
+            Utils::format_date(1_709_251_200 + 13 * 3_600 + 45 * 60),
+            "2024-03-01 13:45"
+        );
+        assert_eq!(Utils::format_date(951_782_400), "2000-02-29 00:00");
+        assert!(Utils::parse_date("2024-13-01").is_err());
+        assert!(Utils::parse_date("yesterday").is_err());
+
+        let week_ago = Utils::parse_date("7d").expect("relative date");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        assert!(now - week_ago >= 7 * 86_400 && now - week_ago < 7 * 86_400 + 60);
+    }
+
     #[test]
     fn command_exists_detects_binaries() {
         // macOS typically ships with /usr/bin/env; treat it as a stable probe.
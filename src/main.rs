@@ -1,11 +1,50 @@
-mod commands;
-use crate::commands::{Interactive, Rust, Simulator, Solana, Status};
-use clap::{Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, error::ErrorKind};
+use cleanup::commands::{
+    Audit, AuditQuery, Cache, CargoBin, CleanupError, Config, Custom, CustomTarget, Env, Filter,
+    History, Interactive, OutputFormat, Reclaim, Removals, Report, Result, Runner, Rust, Scan,
+    Sccache, Simulator, Solana, SolanaLedger, Status, TargetDir, TrimLimits, Tui, Utils,
+};
+use std::{path::PathBuf, time::Duration};
 
 #[derive(Parser)]
 #[command(name = "cleanup")]
 #[command(about = "A Rust-base tool for mac")]
+#[command(
+    after_help = "Custom targets declared in the config file are available as subcommands, e.g. `cleanup <name> --list`."
+)]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Ignore the scan cache and rescan every directory"
+    )]
+    refresh: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECONDS",
+        help = "Timeout for version probes and tool listings [default: 10]"
+    )]
+    timeout: Option<u64>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Config file to use instead of ~/.config/cleanup/config.toml"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Print the removal summary as JSON instead of a table"
+    )]
+    json: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Remove items even if running processes are using them"
+    )]
+    force: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -13,15 +52,42 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Show storage usage summary
-    Status,
+    Status {
+        #[arg(long, help = "Append a snapshot of this scan to the history")]
+        record: bool,
+    },
+    /// Show storage trends from recorded status snapshots
+    History {
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = Utils::parse_date,
+            help = "Show the largest growers since a date, e.g. 2024-05-01 or 7d"
+        )]
+        since: Option<u64>,
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["FROM", "TO"],
+            conflicts_with = "since",
+            help = "Compare two snapshots by their number"
+        )]
+        diff: Option<Vec<usize>>,
+        #[arg(long, default_value_t = 10, help = "Maximum number of rows to show")]
+        limit: usize,
+    },
     /// Interactive cleanup wizard
     Interactive,
+    /// Browse storage usage in a full-screen terminal UI
+    Tui,
     /// Manage Solana installations
     Solana {
         #[arg(short, long, help = "List all installed Solana versions")]
         list: bool,
         #[arg(short, long, help = "Clean up Solana versions")]
         clean: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Manage Rust installations
     Rust {
@@ -29,6 +95,8 @@ enum Commands {
         list: bool,
         #[arg(short, long, help = "Clean up Rust versions")]
         clean: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
     },
     /// Manage Simulator installations
     Simulator {
@@ -36,42 +104,383 @@ enum Commands {
         list: bool,
         #[arg(short, long, help = "Clean up Simulator versions")]
         clean: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Manage binaries installed with `cargo install`
+    #[command(name = "cargo-bin")]
+    CargoBin {
+        #[arg(short, long, help = "List installed crates and their binaries")]
+        list: bool,
+        #[arg(short, long, help = "Uninstall selected crates")]
+        clean: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Inspect and trim the local sccache disk cache
+    Sccache {
+        #[arg(short, long, help = "Show cache size and entry ages")]
+        list: bool,
+        #[arg(
+            long,
+            value_name = "SIZE",
+            value_parser = Utils::parse_size,
+            help = "Remove least recently used entries until the cache fits, e.g. 5GB"
+        )]
+        max_size: Option<u64>,
+        #[arg(
+            long,
+            value_name = "AGE",
+            value_parser = Utils::parse_duration,
+            help = "Remove entries not used for this long, e.g. 30d"
+        )]
+        older_than: Option<Duration>,
+        #[arg(short, long, help = "Trim without asking for confirmation")]
+        yes: bool,
+    },
+    /// Prune cargo target directories by profile, target triple and toolchain
+    #[command(name = "target-dir")]
+    TargetDir {
+        #[arg(
+            value_name = "PATH",
+            help = "Directories to search [default: configured roots or .]"
+        )]
+        paths: Vec<PathBuf>,
+        #[arg(short, long, help = "List target directories broken down by profile")]
+        list: bool,
+        #[arg(short, long, help = "Remove stale build artifacts")]
+        clean: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Find and remove Solana test-ledger directories
+    #[command(name = "solana-ledger")]
+    SolanaLedger {
+        #[arg(
+            value_name = "PATH",
+            help = "Directories to search [default: configured roots or .]"
+        )]
+        paths: Vec<PathBuf>,
+        #[arg(short, long, help = "List test ledgers with size and last run time")]
+        list: bool,
+        #[arg(short, long, help = "Remove ledgers whose validator is not running")]
+        clean: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Show the audit log of removed items
+    Log {
+        #[arg(long, help = "Only show removals from this target, e.g. rust")]
+        target: Option<String>,
+        #[arg(
+            long,
+            value_name = "GLOB",
+            value_parser = |glob: &str| glob::Pattern::new(glob).map_err(|err| err.to_string()),
+            help = "Only show items whose name matches"
+        )]
+        item: Option<glob::Pattern>,
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = Utils::parse_date,
+            help = "Only show removals since a date, e.g. 2024-05-01 or 7d"
+        )]
+        since: Option<u64>,
+        #[arg(long, help = "Only show failed removals")]
+        failed: bool,
+        #[arg(long, default_value_t = 20, help = "Maximum number of entries to show")]
+        limit: usize,
+    },
+    /// Free up space by removing the safest unused items first
+    Reclaim {
+        #[arg(
+            long,
+            value_name = "SIZE",
+            value_parser = Utils::parse_size,
+            help = "Amount of space to free, e.g. 20GB"
+        )]
+        target: u64,
+        #[arg(short, long, help = "Execute the plan without asking for confirmation")]
+        yes: bool,
+        #[arg(long, help = "Also remove available simulator devices")]
+        include_devices: bool,
+    },
+    /// Manage the on-disk scan cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
+    /// Manage a custom target declared in the config file
+    #[command(external_subcommand)]
+    Custom(Vec<String>),
 }
 
-fn main() {
-    let cli = Cli::parse();
+/// list/clean 命令共用的条目名称过滤
+#[derive(Args, Clone, Default)]
+struct FilterArgs {
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Only include items whose name matches"
+    )]
+    include: Vec<String>,
+    #[arg(long, value_name = "GLOB", help = "Skip items whose name matches")]
+    exclude: Vec<String>,
+    #[arg(
+        long,
+        value_name = "AGE",
+        value_parser = Utils::parse_duration,
+        help = "Only include items not modified or accessed for this long, e.g. 30d"
+    )]
+    unused_for: Option<Duration>,
+}
+
+/// 自定义目标子命令的参数
+#[derive(Parser)]
+struct CustomArgs {
+    #[arg(short, long, help = "List all items of this target")]
+    list: bool,
+    #[arg(short, long, help = "Clean up items of this target")]
+    clean: bool,
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+/// 在配置中查找自定义目标后再解析它的参数，未知的名称按未知子命令报错退出
+fn custom_command<'a>(config: &'a Config, args: &[String]) -> (&'a CustomTarget, CustomArgs) {
+    let name = args.first().map(String::as_str).unwrap_or_default();
+    let Some(target) = config.custom_target(name) else {
+        Cli::command()
+            .error(
+                ErrorKind::InvalidSubcommand,
+                format!("unrecognized subcommand '{}'", name),
+            )
+            .exit();
+    };
+    (target, CustomArgs::parse_from(args))
+}
 
-    match cli.command {
-        Commands::Status => {
-            Status::show_status();
+impl Commands {
+    /// 本次命令的 `--include`/`--exclude`
+    fn filter_args(&self, config: &Config) -> FilterArgs {
+        match self {
+            Commands::Solana { filter, .. }
+            | Commands::Rust { filter, .. }
+            | Commands::Simulator { filter, .. }
+            | Commands::CargoBin { filter, .. }
+            | Commands::TargetDir { filter, .. }
+            | Commands::SolanaLedger { filter, .. } => filter.clone(),
+            Commands::Custom(args) => custom_command(config, args).1.filter,
+            _ => FilterArgs::default(),
         }
-        Commands::Interactive => {
-            Interactive::run_wizard();
+    }
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Remove all cached scan results
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective configuration
+    Show,
+}
+
+fn run(command: Commands, env: &Env) -> Result<()> {
+    match command {
+        Commands::Status { record } => Status::show_status(env, record),
+        Commands::History { since, diff, limit } => match (since, diff.as_deref()) {
+            (_, Some(&[from, to])) => History::show_diff(env, from, to),
+            (Some(since), _) => History::show_growers(env, since, limit),
+            _ => History::show_trends(env, limit),
+        },
+        Commands::Interactive => Interactive::run_wizard(env),
+        Commands::Tui => Tui::run(env),
+        Commands::Solana { list, clean, .. } => {
+            if list {
+                Solana::show_solana_versions(env)?;
+            }
+            if clean {
+                Solana::clean_solana_versions(env)?;
+            }
+            Ok(())
         }
-        Commands::Solana { list, clean } => {
+        Commands::Rust { list, clean, .. } => {
             if list {
-                Solana::show_solana_versions();
+                Rust::show_rust_versions(env)?;
             }
             if clean {
-                Solana::clean_solana_versions();
+                Rust::clean_rust_versions(env)?;
             }
+            Ok(())
         }
-        Commands::Rust { list, clean } => {
+        Commands::Simulator { list, clean, .. } => {
             if list {
-                Rust::show_rust_versions();
+                Simulator::show_simulator_versions(env)?;
             }
             if clean {
-                Rust::clean_rust_versions();
+                Simulator::clean_simulators(env)?;
             }
+            Ok(())
         }
-        Commands::Simulator { list, clean } => {
+        Commands::CargoBin { list, clean, .. } => {
             if list {
-                Simulator::show_simulator_versions();
+                CargoBin::show_crates(env)?;
             }
             if clean {
-                Simulator::clean_simulators();
+                CargoBin::clean_crates(env)?;
             }
+            Ok(())
+        }
+        Commands::Sccache {
+            list,
+            max_size,
+            older_than,
+            yes,
+        } => {
+            if list {
+                Sccache::show_cache(env)?;
+            }
+            if max_size.is_some() || older_than.is_some() {
+                Sccache::trim(
+                    env,
+                    TrimLimits {
+                        max_size,
+                        older_than,
+                    },
+                    yes,
+                )?;
+            }
+            Ok(())
+        }
+        Commands::TargetDir {
+            paths, list, clean, ..
+        } => {
+            if list {
+                TargetDir::show_target_dirs(env, &paths)?;
+            }
+            if clean {
+                TargetDir::clean_target_dirs(env, &paths)?;
+            }
+            Ok(())
+        }
+        Commands::SolanaLedger {
+            paths, list, clean, ..
+        } => {
+            if list {
+                SolanaLedger::show_ledgers(env, &paths)?;
+            }
+            if clean {
+                SolanaLedger::clean_ledgers(env, &paths)?;
+            }
+            Ok(())
+        }
+        Commands::Log {
+            target,
+            item,
+            since,
+            failed,
+            limit,
+        } => Audit::show_log(
+            env,
+            &AuditQuery {
+                target,
+                item,
+                since,
+                failed,
+                limit,
+            },
+        ),
+        Commands::Reclaim {
+            target,
+            yes,
+            include_devices,
+        } => Reclaim::reclaim(env, target, yes, include_devices),
+        Commands::Cache { action } => match action {
+            CacheAction::Clear => Cache::clear(),
+        },
+        Commands::Config { action } => match action {
+            ConfigAction::Show => Config::show(env),
+        },
+        Commands::Custom(args) => {
+            let (target, CustomArgs { list, clean, .. }) = custom_command(env.settings(), &args);
+            if list {
+                Custom::show_items(env, target)?;
+            }
+            if clean {
+                Custom::clean_items(env, target)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let env = Env::system();
+    let mut filter = FilterArgs::default();
+    let config = Config::load(&env, cli.config.as_deref()).and_then(|mut config| {
+        filter = cli.command.filter_args(&config);
+        config.filter =
+            Filter::new(&filter.include, &filter.exclude)?.unused_for(filter.unused_for);
+        Ok(config)
+    });
+    let mut config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            Report::record(err);
+            std::process::exit(Report::finish());
         }
     };
+    config.force = cli.force;
+    if cli.json {
+        config.output.format = OutputFormat::Json;
+    }
+    config.apply_output();
+    if config.output.format == OutputFormat::Json {
+        Removals::reserve_stdout();
+    }
+    Runner::set_probe_timeout(Duration::from_secs(cli.timeout.unwrap_or(config.timeout)));
+    let env = env.config(config);
+    // 读取文件不会改变目录的修改时间，缓存中的访问时间可能已过期，按未使用时长过滤时总是重新扫描
+    Cache::init(&env, cli.refresh || filter.unused_for.is_some());
+    Scan::install_interrupt_handler();
+
+    let mut result = run(cli.command, &env);
+    if result.is_ok() && Scan::is_cancelled() {
+        result = Err(CleanupError::Cancelled);
+    }
+
+    Cache::save();
+    Runner::report_failures();
+    if let Err(err) = result {
+        Report::record(err);
+    }
+    std::process::exit(Report::finish());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cleanup::commands::config::BUILTIN_COMMANDS;
+
+    #[test]
+    fn builtin_command_list_matches_cli() {
+        let mut names: Vec<String> = Cli::command()
+            .get_subcommands()
+            .map(|command| command.get_name().to_string())
+            .chain(["help".to_string()])
+            .collect();
+        names.sort();
+        let mut builtin: Vec<&str> = BUILTIN_COMMANDS.to_vec();
+        builtin.sort();
+        assert_eq!(names, builtin);
+    }
 }
@@ -0,0 +1,577 @@
+//! 在隔离的主目录中运行 list/clean 流程，外部命令与交互选择均为预设结果
+
+use cleanup::commands::{
+    Audit, CleanupError, Config, Custom, Env, Filter, History, Interactive, Outcome, Reclaim, Rust,
+    Sccache, ScriptedPrompter, ScriptedRunner, Simulator, Solana, Status, TrimLimits,
+};
+use std::{
+    fs::{self, FileTimes},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// 创建一个临时主目录
+fn create_home(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_nanos();
+    let home = std::env::temp_dir().join(format!("cleanup_flow_{}_{}", name, nanos));
+    fs::create_dir_all(&home).expect("Failed to create temp home");
+    home
+}
+
+fn write_file(path: &Path, len: usize) {
+    fs::create_dir_all(path.parent().expect("file has a parent")).expect("Failed to create dir");
+    fs::write(path, vec![0u8; len]).expect("Failed to write file");
+}
+
+/// 将目录树中所有条目的修改与访问时间设为 `days` 天前
+fn age_tree(root: &Path, days: u64) {
+    let then = SystemTime::now() - Duration::from_secs(days * 86_400);
+    let times = FileTimes::new().set_accessed(then).set_modified(then);
+    let mut paths: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .collect();
+    // 先处理子项，避免父目录的时间被再次更新
+    paths.reverse();
+    for path in paths {
+        fs::File::open(&path)
+            .and_then(|file| file.set_times(times))
+            .expect("Failed to set file times");
+    }
+}
+
+const RUNTIMES_JSON: &str = r#"{"runtimes": [{"name": "iOS 17.0", "identifier": "com.apple.CoreSimulator.SimRuntime.iOS-17-0", "bundlePath": "", "isAvailable": true, "version": "17.0"}]}"#;
+const DEVICES_JSON: &str = r#"{"devices": {"com.apple.CoreSimulator.SimRuntime.iOS-17-0": [{"name": "iPhone 15", "udid": "DEVICE-UDID", "isAvailable": true, "dataPathSize": 4096, "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-15"}]}}"#;
+
+#[test]
+fn rust_clean_uninstalls_selected_toolchain_by_name() {
+    let home = create_home("rust");
+    write_file(
+        &home.join(".rustup/toolchains/stable-x86_64-unknown-linux-gnu/bin/rustc"),
+        128,
+    );
+
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .respond(
+                "rustup show active-toolchain",
+                "stable-x86_64-unknown-linux-gnu (default)",
+            )
+            .respond("rustup uninstall", ""),
+    );
+    let env = Env::with_home(&home)
+        .runner(runner.clone())
+        .prompter(ScriptedPrompter::new().select(&[0]));
+
+    Rust::show_rust_versions(&env).expect("list rust");
+    Rust::clean_rust_versions(&env).expect("clean rust");
+
+    assert!(
+        runner
+            .calls()
+            .contains(&"rustup uninstall stable-x86_64-unknown-linux-gnu".to_string())
+    );
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn rust_clean_offers_only_filtered_unprotected_toolchains() {
+    let home = create_home("rust_filter");
+    for toolchain in [
+        "stable-x86_64-unknown-linux-gnu",
+        "1.75.0-x86_64-unknown-linux-gnu",
+        "1.76.0-x86_64-unknown-linux-gnu",
+    ] {
+        write_file(
+            &home
+                .join(".rustup/toolchains")
+                .join(toolchain)
+                .join("bin/rustc"),
+            32,
+        );
+    }
+
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .respond("rustup show active-toolchain", "stable (default)")
+            .respond("rustup uninstall", ""),
+    );
+    let env = Env::with_home(&home)
+        .runner(runner.clone())
+        .prompter(ScriptedPrompter::new().select(&[0]))
+        .config(Config {
+            protect: vec!["1.75.0-*".to_string()],
+            filter: Filter::new(&["1.7*".to_string()], &[]).expect("globs"),
+            ..Config::default()
+        });
+
+    Rust::clean_rust_versions(&env).expect("clean rust");
+
+    let uninstalls: Vec<String> = runner
+        .calls()
+        .into_iter()
+        .filter(|call| call.starts_with("rustup uninstall"))
+        .collect();
+    assert_eq!(
+        uninstalls,
+        vec!["rustup uninstall 1.76.0-x86_64-unknown-linux-gnu".to_string()]
+    );
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[cfg(unix)]
+#[test]
+fn solana_clean_removes_only_inactive_releases() {
+    let home = create_home("solana");
+    let install = home.join(".local/share/solana/install");
+    write_file(
+        &install.join("releases/1.18.4/solana-release/bin/solana"),
+        64,
+    );
+    write_file(
+        &install.join("releases/1.17.0/solana-release/bin/solana"),
+        64,
+    );
+    std::os::unix::fs::symlink(
+        install.join("releases/1.18.4/solana-release"),
+        install.join("active_release"),
+    )
+    .expect("Failed to link active release");
+
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .prompter(ScriptedPrompter::new().select(&[0]));
+
+    Solana::show_solana_versions(&env).expect("list solana");
+    Solana::clean_solana_versions(&env).expect("clean solana");
+
+    assert!(!install.join("releases/1.17.0").exists());
+    assert!(install.join("releases/1.18.4").exists());
+
+    let log = Audit::load(&env).expect("audit log");
+    assert_eq!(log.len(), 1);
+    assert_eq!(
+        (log[0].target.as_str(), log[0].item.as_str(), log[0].outcome),
+        ("solana", "1.17.0", Outcome::Removed)
+    );
+    assert_eq!(
+        log[0].path.as_deref(),
+        Some(install.join("releases/1.17.0").as_path())
+    );
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[cfg(unix)]
+#[test]
+fn solana_clean_never_offers_protected_releases() {
+    let home = create_home("solana_protect");
+    let install = home.join(".local/share/solana/install");
+    for version in ["1.16.0", "1.17.0", "1.18.4"] {
+        write_file(
+            &install
+                .join("releases")
+                .join(version)
+                .join("solana-release/bin/solana"),
+            64,
+        );
+    }
+    std::os::unix::fs::symlink(
+        install.join("releases/1.18.4/solana-release"),
+        install.join("active_release"),
+    )
+    .expect("Failed to link active release");
+
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .prompter(ScriptedPrompter::new().select(&[0]))
+        .config(Config {
+            protect: vec!["1.16.0".to_string()],
+            ..Config::default()
+        });
+
+    Solana::clean_solana_versions(&env).expect("clean solana");
+
+    assert!(install.join("releases/1.16.0").exists());
+    assert!(!install.join("releases/1.17.0").exists());
+    assert!(install.join("releases/1.18.4").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn custom_target_clean_keeps_items_with_active_marker() {
+    let home = create_home("custom");
+    let dumps = home.join("fixtures");
+    write_file(&dumps.join("dump-2024-01/data.bin"), 128);
+    write_file(&dumps.join("dump-2024-02/data.bin"), 128);
+    write_file(&dumps.join("dump-2024-02/.in-use"), 0);
+    write_file(&dumps.join("README"), 16);
+
+    let config: Config = toml::from_str(
+        r#"
+        [[custom]]
+        name = "fixtures"
+        root = "~/fixtures"
+        pattern = "dump-*"
+        active = ".in-use"
+        "#,
+    )
+    .expect("valid config");
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .prompter(ScriptedPrompter::new().select(&[0]))
+        .config(config);
+    let target = env.settings().custom_target("fixtures").expect("target");
+
+    Custom::show_items(&env, target).expect("list fixtures");
+    Custom::clean_items(&env, target).expect("clean fixtures");
+
+    assert!(!dumps.join("dump-2024-01").exists());
+    assert!(dumps.join("dump-2024-02").exists());
+    assert!(dumps.join("README").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn unused_for_only_offers_stale_items() {
+    let home = create_home("unused");
+    let cache = home.join("snapshots");
+    write_file(&cache.join("old/ledger.bin"), 64);
+    write_file(&cache.join("fresh/ledger.bin"), 64);
+    age_tree(&cache.join("old"), 90);
+
+    let mut config: Config = toml::from_str(
+        r#"
+        [[custom]]
+        name = "snapshots"
+        root = "~/snapshots"
+        "#,
+    )
+    .expect("valid config");
+    config.filter = Filter::default().unused_for(Some(Duration::from_secs(30 * 86_400)));
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .prompter(ScriptedPrompter::new().select(&[0]))
+        .config(config);
+    let target = env.settings().custom_target("snapshots").expect("target");
+
+    let items = Custom::get_items(&env, target).expect("snapshots");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "old");
+
+    Custom::clean_items(&env, target).expect("clean snapshots");
+    assert!(!cache.join("old").exists());
+    assert!(cache.join("fresh").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn reclaim_removes_oldest_items_until_target_after_confirmation() {
+    let home = create_home("reclaim");
+    let models = home.join("models");
+    for (name, days) in [("oldest", 300), ("older", 200), ("newest", 1)] {
+        write_file(&models.join(name).join("weights.bin"), 64 * 1024);
+        age_tree(&models.join(name), days);
+    }
+    let mut config: Config = toml::from_str(
+        r#"
+        [[custom]]
+        name = "models"
+        root = "~/models"
+        "#,
+    )
+    .expect("valid config");
+    config.rust.enabled = false;
+    config.simulator.enabled = false;
+
+    let declined = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .prompter(ScriptedPrompter::new().confirm(false))
+        .config(config.clone());
+    Reclaim::reclaim(&declined, 100 * 1024, false, false).expect("declined reclaim");
+    assert!(models.join("oldest").exists());
+
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .config(config);
+    Reclaim::reclaim(&env, 100 * 1024, true, false).expect("reclaim");
+
+    assert!(!models.join("oldest").exists());
+    assert!(!models.join("older").exists());
+    assert!(models.join("newest").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn json_output_keeps_stdout_parseable() {
+    let home = create_home("json");
+    write_file(&home.join("models/old/weights.bin"), 64 * 1024);
+    age_tree(&home.join("models/old"), 100);
+    let config = home.join("config.toml");
+    fs::write(
+        &config,
+        r#"
+        [rust]
+        enabled = false
+        [solana]
+        enabled = false
+        [simulator]
+        enabled = false
+        [cargo_bin]
+        enabled = false
+        [sccache]
+        enabled = false
+
+        [[custom]]
+        name = "models"
+        root = "~/models"
+        "#,
+    )
+    .expect("Failed to write config");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cleanup"))
+        .args(["--json", "--config"])
+        .arg(&config)
+        .args(["reclaim", "--target", "1KB", "--yes"])
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_CACHE_HOME")
+        .env_remove("XDG_DATA_HOME")
+        .output()
+        .expect("Failed to run cleanup");
+
+    assert!(output.status.success(), "{:?}", output);
+    let summary: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout is a single JSON document");
+    assert_eq!(summary["removed"], 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Removing old"));
+    assert!(!home.join("models/old").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn unknown_custom_target_is_reported_before_its_arguments() {
+    let home = create_home("unknown_target");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cleanup"))
+        .args(["bogus", "--bogus"])
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .output()
+        .expect("Failed to run cleanup");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unrecognized subcommand 'bogus'"),
+        "{}",
+        stderr
+    );
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn sccache_trim_removes_expired_then_least_recently_used_entries() {
+    let home = create_home("sccache");
+    let cache = home.join("sccache");
+    for (name, days) in [("a/0/expired", 60), ("b/1/stale", 20), ("c/2/recent", 2)] {
+        write_file(&cache.join(name), 64 * 1024);
+        age_tree(&cache.join(name), days);
+    }
+    let env = Env::with_home(&home)
+        .var_override("SCCACHE_DIR", &cache.to_string_lossy())
+        .runner(ScriptedRunner::new());
+
+    let by_age = TrimLimits {
+        older_than: Some(Duration::from_secs(30 * 86_400)),
+        ..TrimLimits::default()
+    };
+    Sccache::trim(&env, by_age, true).expect("trim by age");
+    assert!(!cache.join("a/0/expired").exists());
+    assert!(cache.join("b/1/stale").exists());
+
+    let by_size = TrimLimits {
+        max_size: Some(100 * 1024),
+        ..TrimLimits::default()
+    };
+    Sccache::trim(&env, by_size, true).expect("trim by size");
+    assert!(!cache.join("b/1/stale").exists());
+    assert!(cache.join("c/2/recent").exists());
+
+    let entries = Audit::load(&env).expect("audit log");
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|entry| entry.target == "sccache"));
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn wizard_preselects_old_items_and_removes_them_in_one_pass() {
+    let home = create_home("wizard");
+    let models = home.join("models");
+    write_file(&models.join("stale/weights.bin"), 32 * 1024);
+    write_file(&models.join("fresh/weights.bin"), 32 * 1024);
+    age_tree(&models.join("stale"), 90);
+    let mut config: Config = toml::from_str(
+        r#"
+        [[custom]]
+        name = "models"
+        root = "~/models"
+        "#,
+    )
+    .expect("valid config");
+    config.rust.enabled = false;
+    config.simulator.enabled = false;
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .prompter(ScriptedPrompter::new().accept_defaults().confirm(true))
+        .config(config);
+
+    Interactive::run_wizard(&env).expect("wizard");
+
+    assert!(!models.join("stale").exists());
+    assert!(models.join("fresh").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn disabled_targets_are_not_scanned() {
+    let home = create_home("disabled");
+    let runner = Arc::new(ScriptedRunner::new());
+    let mut config = Config::default();
+    config.rust.enabled = false;
+    config.simulator.enabled = false;
+    let env = Env::with_home(&home).runner(runner.clone()).config(config);
+
+    Rust::clean_rust_versions(&env).expect("clean rust");
+    Status::show_status(&env, false).expect("status");
+
+    assert!(runner.calls().is_empty());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn simulator_clean_deletes_devices_and_empties_caches() {
+    let home = create_home("simulator");
+    let caches = home.join("Library/Developer/CoreSimulator/Caches");
+    write_file(&caches.join("dyld/cache.bin"), 256);
+
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .respond("xcrun simctl list runtimes -j", RUNTIMES_JSON)
+            .respond("xcrun simctl list devices -j", DEVICES_JSON)
+            .respond("xcrun simctl delete", ""),
+    );
+    // 列表顺序：运行时、设备、缓存目录
+    let env = Env::with_home(&home)
+        .runner(runner.clone())
+        .prompter(ScriptedPrompter::new().select(&[1, 2]));
+
+    Simulator::show_simulator_versions(&env).expect("list simulators");
+    Simulator::clean_simulators(&env).expect("clean simulators");
+
+    assert!(
+        runner
+            .calls()
+            .contains(&"xcrun simctl delete DEVICE-UDID".to_string())
+    );
+    assert!(caches.exists());
+    assert_eq!(fs::read_dir(&caches).expect("caches dir").count(), 0);
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn status_runs_without_any_tools_installed() {
+    let home = create_home("status");
+    let env = Env::with_home(&home).runner(ScriptedRunner::new());
+
+    Status::show_status(&env, false).expect("status");
+
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn status_records_history_snapshots_when_asked() {
+    let home = create_home("history");
+    write_file(&home.join("models/llama/weights.bin"), 8 * 1024);
+    let mut config: Config = toml::from_str(
+        r#"
+        [[custom]]
+        name = "models"
+        root = "~/models"
+        "#,
+    )
+    .expect("valid config");
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .config(config.clone());
+
+    Status::show_status(&env, false).expect("status");
+    assert!(History::load(&env).expect("history").is_empty());
+
+    Status::show_status(&env, true).expect("status --record");
+    config.history.record = true;
+    let env = Env::with_home(&home)
+        .runner(ScriptedRunner::new())
+        .config(config);
+    Status::show_status(&env, false).expect("status with history.record");
+
+    let snapshots = History::load(&env).expect("history");
+    assert_eq!(snapshots.len(), 2);
+    let models = &snapshots[1].categories["models"];
+    assert!(models.items["llama"] >= 8 * 1024);
+    assert!(home.join(".local/share/cleanup/history.jsonl").exists());
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn rust_list_without_rustup_reports_missing_tool() {
+    let home = create_home("no_rustup");
+    let env = Env::with_home(&home).runner(ScriptedRunner::new());
+
+    let err = Rust::show_rust_versions(&env).expect_err("rustup is missing");
+    assert!(matches!(err, CleanupError::MissingTool(ref tool) if tool == "rustup"));
+    assert_eq!(err.exit_code(), 3);
+
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}
+
+#[test]
+fn failed_uninstall_is_reported_as_partial_deletion() {
+    let home = create_home("rust_fail");
+    write_file(
+        &home.join(".rustup/toolchains/1.75.0-x86_64-unknown-linux-gnu/bin/rustc"),
+        16,
+    );
+    let env = Env::with_home(&home)
+        .runner(
+            ScriptedRunner::new()
+                .respond("rustup show active-toolchain", "stable (default)")
+                .fail("rustup uninstall", "error: toolchain is in use"),
+        )
+        .prompter(ScriptedPrompter::new().select(&[0]));
+
+    match Rust::clean_rust_versions(&env) {
+        Err(CleanupError::PartialDeletion {
+            attempted,
+            failures,
+        }) => {
+            assert_eq!(attempted, 1);
+            assert!(failures[0].1.contains("toolchain is in use"));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let log = Audit::load(&env).expect("audit log");
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].outcome, Outcome::Failed);
+    assert_eq!(
+        log[0].command.as_deref(),
+        Some("rustup uninstall 1.75.0-x86_64-unknown-linux-gnu")
+    );
+    assert_eq!(log[0].stderr.as_deref(), Some("error: toolchain is in use"));
+
+    fs::remove_dir_all(&home).expect("Failed to remove temp home");
+}